@@ -0,0 +1,430 @@
+use crate::chunks::*;
+use crate::error::{MdxError, MdxErrorKind};
+use crate::mdlx::MDLXModel;
+use std::ops::RangeInclusive;
+
+// Geometry for ModelBuilder::add_geoset, one entry per vertex in every per vertex list.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    // Empty for zero normals
+    pub normals: Vec<[f32; 3]>,
+    // Empty for zero texture coordinates
+    pub uvs: Vec<[f32; 2]>,
+    pub faces: Vec<[u16; 3]>,
+    pub material_id: u32,
+    // Object ids of the bones the whole mesh follows
+    pub bones: Vec<u32>,
+}
+
+// Builds a model from scratch. The add_* methods return the id other objects refer to it by:
+// texture, material, geoset and sequence ids are indexes, bones get the next object id.
+pub struct ModelBuilder {
+    model: MDLXModel,
+    object_count: u32,
+}
+
+impl ModelBuilder {
+    pub fn new(name: &str) -> ModelBuilder {
+        let model = MDLXModel {
+            version_chunk: Some(VersionChunk { version: 800 }),
+            model_chunk: Some(ModelChunk {
+                name: name.to_string(),
+                unknown: 0,
+                extent: extent_of(&[]),
+                blend_time: 150,
+            }),
+            ..MDLXModel::default()
+        };
+        ModelBuilder {
+            model,
+            object_count: 0,
+        }
+    }
+
+    pub fn version(&mut self, version: u32) -> &mut Self {
+        self.model.version_chunk = Some(VersionChunk { version });
+        self
+    }
+
+    pub fn blend_time(&mut self, blend_time: u32) -> &mut Self {
+        if let Some(model_chunk) = self.model.model_chunk.as_mut() {
+            model_chunk.blend_time = blend_time;
+        }
+        self
+    }
+
+    pub fn add_texture(&mut self, file_name: &str) -> u32 {
+        self.push_texture(0, file_name)
+    }
+
+    // Team color, team glow and the like, 1 for team color
+    pub fn add_replaceable_texture(&mut self, replaceable_id: u32) -> u32 {
+        self.push_texture(replaceable_id, "")
+    }
+
+    fn push_texture(&mut self, replaceable_id: u32, file_name: &str) -> u32 {
+        let textures = self
+            .model
+            .texture_chunk
+            .get_or_insert_with(|| TextureChunk { data: Vec::new() });
+        textures.data.push(Texture {
+            replaceable_id,
+            file_name: file_name.to_string(),
+            unknown: 0,
+            flags: 0,
+        });
+        textures.data.len() as u32 - 1
+    }
+
+    // Material with a single layer, filter_mode as in Layer
+    pub fn add_material(&mut self, texture_id: u32, filter_mode: u32) -> u32 {
+        let layer = Layer {
+            filter_mode,
+            shading_flags: 0,
+            texture_id,
+            texture_animation_id: 0xFFFF_FFFF,
+            coord_id: 0,
+            alpha: 1.0,
+            hd: None,
+            unknown_bytes: None,
+            texture_id_transform: None,
+            alpha_transform: None,
+            emissive_gain_transform: None,
+            fresnel_color_transform: None,
+            fresnel_alpha_transform: None,
+            fresnel_team_color_transform: None,
+            unknown_tracks: None,
+        };
+        let materials = self
+            .model
+            .material_chunk
+            .get_or_insert_with(|| MaterialChunk { data: Vec::new() });
+        materials.data.push(Material {
+            priority_plane: 0,
+            flags: 0,
+            shader: None,
+            layers: vec![layer],
+        });
+        materials.data.len() as u32 - 1
+    }
+
+    pub fn add_geoset(&mut self, mesh: Mesh) -> u32 {
+        let vertex_count = mesh.positions.len();
+        let mut normals = mesh.normals;
+        let mut uvs = mesh.uvs;
+        if normals.is_empty() {
+            normals = vec![[0.0; 3]; vertex_count];
+        }
+        if uvs.is_empty() {
+            uvs = vec![[0.0; 2]; vertex_count];
+        }
+
+        let geoset = Geoset {
+            extent: extent_of(&mesh.positions),
+            vertex_positions: mesh
+                .positions
+                .into_iter()
+                .map(|position| VertexPosition { position })
+                .collect(),
+            vertex_normals: normals
+                .into_iter()
+                .map(|normal| VertexNormal { normal })
+                .collect(),
+            // A single triangle list
+            face_type_groups: vec![FaceTypeGroup { face_type: 4 }],
+            face_groups: vec![FaceGroup {
+                number_of_indexes: mesh.faces.len() as u32 * 3,
+            }],
+            faces: mesh
+                .faces
+                .iter()
+                .map(|face| Face {
+                    index1: face[0],
+                    index2: face[1],
+                    index3: face[2],
+                })
+                .collect(),
+            // Every vertex in the one matrix group
+            vertex_groups: (0..vertex_count)
+                .map(|_| VertexGroup { matrix_group: 0 })
+                .collect(),
+            matrix_groups: vec![MatrixGroup {
+                matrix_group_size: mesh.bones.len() as u32,
+            }],
+            matrix_indexes: mesh
+                .bones
+                .iter()
+                .map(|&matrix_index| MatrixIndex { matrix_index })
+                .collect(),
+            material_id: mesh.material_id,
+            selection_group: 0,
+            selection_type: 0,
+            lod: None,
+            extent_sequences: Vec::new(),
+            tangents: None,
+            skin: None,
+            texture_coordinate_sets: vec![TextureCoordinateSet {
+                texture_coordinates: uvs,
+            }],
+        };
+
+        let geosets = self
+            .model
+            .geoset_chunk
+            .get_or_insert_with(|| GeosetChunk { data: Vec::new() });
+        geosets.data.push(geoset);
+        geosets.data.len() as u32 - 1
+    }
+
+    pub fn add_bone(&mut self, name: &str, parent: Option<u32>) -> u32 {
+        self.add_bone_at(name, parent, [0.0; 3])
+    }
+
+    pub fn add_bone_at(&mut self, name: &str, parent: Option<u32>, pivot: [f32; 3]) -> u32 {
+        let object_id = self.object_count;
+        self.object_count += 1;
+
+        let bone = Bone {
+            node: Node {
+                name: name.to_string(),
+                object_id,
+                parent_id: parent.unwrap_or(0xFFFF_FFFF),
+                flags: BONE_FLAG,
+                translation: None,
+                rotation: None,
+                scaling: None,
+                unknown_tracks: None,
+            },
+            geoset_id: 0xFFFF_FFFF,
+            geoset_animation_id: 0xFFFF_FFFF,
+        };
+        self.model
+            .bone_chunk
+            .get_or_insert_with(|| BoneChunk { data: Vec::new() })
+            .data
+            .push(bone);
+
+        // Pivot points are indexed by object id
+        self.model
+            .pivot_point_chunk
+            .get_or_insert_with(|| PivotPointChunk { data: Vec::new() })
+            .data
+            .push(PivotPoint { position: pivot });
+        object_id
+    }
+
+    pub fn add_sequence(&mut self, name: &str, range: RangeInclusive<u32>) -> u32 {
+        let sequences = self
+            .model
+            .sequence_chunk
+            .get_or_insert_with(|| SequenceChunk { data: Vec::new() });
+        sequences.data.push(Sequence {
+            name: name.to_string(),
+            interval_start: *range.start(),
+            interval_end: *range.end(),
+            move_speed: 0.0,
+            non_looping: 0,
+            rarity: 0.0,
+            unknown: 0,
+            extent: extent_of(&[]),
+        });
+        sequences.data.len() as u32 - 1
+    }
+
+    // Checks every id handed in and fills in the extents.
+    pub fn build(mut self) -> Result<MDLXModel, MdxError> {
+        self.check()?;
+
+        let model = &mut self.model;
+        let sequence_count = model
+            .sequence_chunk
+            .as_ref()
+            .map_or(0, |sequences| sequences.data.len());
+        let mut positions = Vec::new();
+        if let Some(geosets) = model.geoset_chunk.as_mut() {
+            for geoset in geosets.data.iter_mut() {
+                geoset.extent_sequences = vec![geoset.extent.clone(); sequence_count];
+                positions.extend(geoset.vertex_positions.iter().map(|v| v.position));
+            }
+        }
+
+        let extent = extent_of(&positions);
+        if let Some(sequences) = model.sequence_chunk.as_mut() {
+            for sequence in sequences.data.iter_mut() {
+                sequence.extent = extent.clone();
+            }
+        }
+        if let Some(model_chunk) = model.model_chunk.as_mut() {
+            model_chunk.extent = extent;
+        }
+
+        model.match_version()?;
+        Ok(self.model)
+    }
+
+    fn check(&self) -> Result<(), MdxError> {
+        let model = &self.model;
+        if let Some(model_chunk) = model.model_chunk.as_ref() {
+            check_name(&model_chunk.name, 336).map_err(|e| e.within(0, "model"))?;
+        }
+        if let Some(textures) = model.texture_chunk.as_ref() {
+            for (i, texture) in textures.data.iter().enumerate() {
+                check_name(&texture.file_name, 256)
+                    .map_err(|e| e.within(0, format!("texture {}", i)))?;
+            }
+        }
+        let texture_count = model
+            .texture_chunk
+            .as_ref()
+            .map_or(0, |textures| textures.data.len());
+        let material_count = model
+            .material_chunk
+            .as_ref()
+            .map_or(0, |materials| materials.data.len());
+
+        if let Some(materials) = model.material_chunk.as_ref() {
+            for (i, material) in materials.data.iter().enumerate() {
+                for layer in &material.layers {
+                    if layer.texture_id as usize >= texture_count {
+                        return Err(
+                            invalid(format!("texture {} doesn't exist", layer.texture_id))
+                                .within(0, format!("material {}", i)),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(geosets) = model.geoset_chunk.as_ref() {
+            for (i, geoset) in geosets.data.iter().enumerate() {
+                self.check_geoset(geoset, material_count)
+                    .map_err(|e| e.within(0, format!("geoset {}", i)))?;
+            }
+        }
+
+        if let Some(bones) = model.bone_chunk.as_ref() {
+            for (i, bone) in bones.data.iter().enumerate() {
+                check_name(&bone.node.name, 80).map_err(|e| e.within(0, format!("bone {}", i)))?;
+                let parent_id = bone.node.parent_id;
+                if parent_id != 0xFFFF_FFFF && parent_id >= self.object_count {
+                    return Err(invalid(format!("parent {} doesn't exist", parent_id))
+                        .within(0, format!("bone {}", i)));
+                }
+            }
+        }
+
+        if let Some(sequences) = model.sequence_chunk.as_ref() {
+            for (i, sequence) in sequences.data.iter().enumerate() {
+                check_name(&sequence.name, 80)
+                    .map_err(|e| e.within(0, format!("sequence {}", i)))?;
+                if sequence.interval_start > sequence.interval_end {
+                    return Err(invalid("interval ends before it starts".to_string())
+                        .within(0, format!("sequence {}", i)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_geoset(&self, geoset: &Geoset, material_count: usize) -> Result<(), MdxError> {
+        let vertex_count = geoset.vertex_positions.len();
+        if geoset.vertex_normals.len() != vertex_count {
+            return Err(invalid(format!(
+                "{} normals for {} vertices",
+                geoset.vertex_normals.len(),
+                vertex_count
+            )));
+        }
+        for set in &geoset.texture_coordinate_sets {
+            if set.texture_coordinates.len() != vertex_count {
+                return Err(invalid(format!(
+                    "{} texture coordinates for {} vertices",
+                    set.texture_coordinates.len(),
+                    vertex_count
+                )));
+            }
+        }
+        for face in &geoset.faces {
+            for &index in &[face.index1, face.index2, face.index3] {
+                if index as usize >= vertex_count {
+                    return Err(invalid(format!("vertex {} doesn't exist", index)));
+                }
+            }
+        }
+        if geoset.material_id as usize >= material_count {
+            return Err(invalid(format!(
+                "material {} doesn't exist",
+                geoset.material_id
+            )));
+        }
+        if geoset.matrix_indexes.is_empty() {
+            return Err(invalid("isn't attached to any bone".to_string()));
+        }
+        for matrix_index in &geoset.matrix_indexes {
+            if matrix_index.matrix_index >= self.object_count {
+                return Err(invalid(format!(
+                    "bone {} doesn't exist",
+                    matrix_index.matrix_index
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> MdxError {
+    MdxError::new(0, MdxErrorKind::InvalidValue(message))
+}
+
+// Names sit in fixed size fields and need room for the terminating NUL
+fn check_name(name: &str, field_len: usize) -> Result<(), MdxError> {
+    if name.len() >= field_len {
+        return Err(invalid(format!(
+            "name is {} bytes, it has to fit in {} with its terminator",
+            name.len(),
+            field_len
+        )));
+    }
+    Ok(())
+}
+
+// Bounding box of the positions and the radius of the sphere around its center
+fn extent_of(positions: &[[f32; 3]]) -> Extent {
+    let mut minimum = [0.0f32; 3];
+    let mut maximum = [0.0f32; 3];
+    if let Some(first) = positions.first() {
+        minimum = *first;
+        maximum = *first;
+    }
+    for position in positions {
+        for axis in 0..3 {
+            minimum[axis] = minimum[axis].min(position[axis]);
+            maximum[axis] = maximum[axis].max(position[axis]);
+        }
+    }
+
+    let center: Vec<f32> = (0..3)
+        .map(|axis| (minimum[axis] + maximum[axis]) / 2.0)
+        .collect();
+    let bounds_radius = positions
+        .iter()
+        .map(|position| {
+            (0..3)
+                .map(|axis| (position[axis] - center[axis]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        })
+        .fold(0.0, f32::max);
+
+    let vec3 = |v: [f32; 3]| Vec3 {
+        x: v[0],
+        y: v[1],
+        z: v[2],
+    };
+    Extent {
+        bounds_radius,
+        minimum: vec3(minimum),
+        maximum: vec3(maximum),
+    }
+}
@@ -1,5 +1,6 @@
-use crate::chunks::{BytesTotalSize, Node, Transform};
+use crate::chunks::{BytesTotalSize, Node, Transform, UnknownTracks};
 use crate::consts::KATV_TAG;
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
@@ -7,15 +8,13 @@ use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct AttachmentChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Attachment>,
 }
 
-calculate_chunk_size_impl!(AttachmentChunk);
+chunk_size_impl!(AttachmentChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for AttachmentChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -25,16 +24,16 @@ impl ctx::TryFromCtx<'_, Endian> for AttachmentChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let attachment = src.gread_with::<Attachment>(offset, ctx)?;
-            total_size += attachment.inclusive_size;
-            assert_eq!(
-                attachment.inclusive_size,
-                attachment.total_bytes_size() as u32
-            );
+            let start = *offset;
+            let attachment: Attachment =
+                read_within(src, offset, ctx, || format!("attachment {}", data.len()))?;
+            total_size += (*offset - start) as u32;
             data.push(attachment);
         }
 
-        Ok((AttachmentChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((AttachmentChunk { data }, *offset))
     }
 }
 
@@ -44,7 +43,7 @@ impl ctx::TryIntoCtx<Endian> for AttachmentChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for geoset in self.data {
             src.gwrite_with::<Attachment>(geoset, offset, ctx)?;
@@ -58,7 +57,8 @@ impl BytesTotalSize for AttachmentChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for attachment in &self.data {
             result += attachment.total_bytes_size();
@@ -70,25 +70,26 @@ impl BytesTotalSize for AttachmentChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct Attachment {
-    pub inclusive_size: u32,
-
     pub node: Node,
     // max length 260
     pub path: String,
     pub attachment_id: u32,
 
     pub visibility: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(Attachment);
+
 impl ctx::TryFromCtx<'_, Endian> for Attachment {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let node = src.gread_with::<Node>(offset, ctx)?;
+        let node: Node = read_at(src, offset, ctx)?;
 
         let max_name_len = 260usize;
         let path = src.gread::<&str>(&mut offset.clone())?.to_string();
@@ -97,25 +98,31 @@ impl ctx::TryFromCtx<'_, Endian> for Attachment {
         let attachment_id = src.gread_with::<u32>(offset, ctx)?;
 
         let mut attachment = Attachment {
-            inclusive_size,
             node,
             path,
             attachment_id,
             visibility: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx).unwrap();
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KATV_TAG => {
-                    let visibility = src.gread_with::<Transform<f32>>(offset, ctx)?;
+                    let visibility = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     attachment.visibility = Some(visibility);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    attachment.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, attachment.total_bytes_size())?;
+
         Ok((attachment, *offset))
     }
 }
@@ -126,7 +133,7 @@ impl ctx::TryIntoCtx<Endian> for Attachment {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
         src.gwrite_with::<Node>(self.node, offset, ctx)?;
 
         // String has fixed size
@@ -144,6 +151,10 @@ impl ctx::TryIntoCtx<Endian> for Attachment {
             src.gwrite_with::<u32>(KATV_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<f32>>(self.visibility.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -153,7 +164,8 @@ impl BytesTotalSize for Attachment {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         result += self.node.total_bytes_size();
 
@@ -166,6 +178,10 @@ impl BytesTotalSize for Attachment {
             result += 4;
             result += self.visibility.as_ref().unwrap().total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -0,0 +1,118 @@
+use crate::chunks::BytesTotalSize;
+use crate::error::{check_size, read_count, read_within, MdxError};
+use scroll::{ctx, Endian, Pread, Pwrite};
+use std::mem::{size_of, size_of_val};
+
+#[derive(PartialEq, Debug)]
+pub struct BindPoseChunk {
+    // Indexed by object id
+    pub data: Vec<BindMatrix>,
+}
+
+chunk_size_impl!(BindPoseChunk);
+
+impl BindPoseChunk {
+    pub fn bind_matrix(&self, object_id: u32) -> Option<&BindMatrix> {
+        self.data.get(object_id as usize)
+    }
+}
+
+impl ctx::TryFromCtx<'_, Endian> for BindPoseChunk {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let chunk_size = src.gread_with::<u32>(offset, ctx)?;
+        let matrices_count = read_count(src, offset, ctx, size_of::<BindMatrix>())?;
+
+        let mut data = Vec::new();
+        for _ in 0..matrices_count {
+            let matrix: BindMatrix =
+                read_within(src, offset, ctx, || format!("matrix {}", data.len()))?;
+            data.push(matrix);
+        }
+
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((BindPoseChunk { data }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for BindPoseChunk {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
+        src.gwrite_with::<u32>(self.data.len() as u32, offset, ctx)?;
+
+        for matrix in self.data {
+            src.gwrite_with::<BindMatrix>(matrix, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for BindPoseChunk {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // chunk_size
+        result += 4;
+        // matrices_count
+        result += 4;
+
+        for matrix in &self.data {
+            result += matrix.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+// 3x4 matrix, columns of the rotation part followed by the translation.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BindMatrix {
+    pub matrix: [f32; 12],
+}
+
+impl ctx::TryFromCtx<'_, Endian> for BindMatrix {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let mut matrix = [0f32; 12];
+        for value in matrix.iter_mut() {
+            *value = src.gread_with::<f32>(offset, ctx)?;
+        }
+
+        Ok((BindMatrix { matrix }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for BindMatrix {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        for value in self.matrix.iter() {
+            src.gwrite_with::<f32>(*value, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for BindMatrix {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        result += size_of_val(&self.matrix);
+
+        result
+    }
+}
@@ -1,18 +1,17 @@
 use crate::chunks::{BytesTotalSize, Node};
+use crate::error::{check_size, read_at, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct BoneChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Bone>,
 }
 
-calculate_chunk_size_impl!(BoneChunk);
+chunk_size_impl!(BoneChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for BoneChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -21,12 +20,15 @@ impl ctx::TryFromCtx<'_, Endian> for BoneChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let bone = src.gread_with::<Bone>(offset, ctx)?;
-            total_size += bone.node.inclusive_size + 4 + 4;
+            let start = *offset;
+            let bone: Bone = read_within(src, offset, ctx, || format!("bone {}", data.len()))?;
+            total_size += (*offset - start) as u32;
             data.push(bone);
         }
 
-        Ok((BoneChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((BoneChunk { data }, *offset))
     }
 }
 
@@ -36,7 +38,7 @@ impl ctx::TryIntoCtx<Endian> for BoneChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for bone in self.data {
             src.gwrite_with::<Bone>(bone, offset, ctx)?;
@@ -50,7 +52,8 @@ impl BytesTotalSize for BoneChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for bone in &self.data {
             result += bone.total_bytes_size();
@@ -68,11 +71,11 @@ pub struct Bone {
 }
 
 impl ctx::TryFromCtx<'_, Endian> for Bone {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
-        let node = src.gread_with::<Node>(offset, ctx)?;
+        let node: Node = read_at(src, offset, ctx)?;
         let geoset_id = src.gread_with::<u32>(offset, ctx)?;
         let geoset_animation_id = src.gread_with::<u32>(offset, ctx)?;
 
@@ -1,5 +1,6 @@
-use crate::chunks::{BytesTotalSize, Transform, Vec3};
+use crate::chunks::{BytesTotalSize, Transform, UnknownTracks, Vec3};
 use crate::consts::{KCRL_TAG, KCTR_TAG, KTTR_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
@@ -7,15 +8,13 @@ use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct CameraChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Camera>,
 }
 
-calculate_chunk_size_impl!(CameraChunk);
+chunk_size_impl!(CameraChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for CameraChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -25,13 +24,16 @@ impl ctx::TryFromCtx<'_, Endian> for CameraChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let camera = src.gread_with::<Camera>(offset, ctx)?;
-            total_size += camera.inclusive_size;
-            assert_eq!(camera.inclusive_size, camera.total_bytes_size() as u32);
+            let start = *offset;
+            let camera: Camera =
+                read_within(src, offset, ctx, || format!("camera {}", data.len()))?;
+            total_size += (*offset - start) as u32;
             data.push(camera);
         }
 
-        Ok((CameraChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((CameraChunk { data }, *offset))
     }
 }
 
@@ -41,7 +43,7 @@ impl ctx::TryIntoCtx<Endian> for CameraChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for camera in self.data {
             src.gwrite_with::<Camera>(camera, offset, ctx)?;
@@ -55,7 +57,8 @@ impl BytesTotalSize for CameraChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for camera in &self.data {
             result += camera.total_bytes_size();
@@ -67,8 +70,6 @@ impl BytesTotalSize for CameraChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct Camera {
-    pub inclusive_size: u32,
-
     // max length 80
     pub name: String,
     pub position: [f32; 3],
@@ -80,10 +81,13 @@ pub struct Camera {
     pub translation: Option<Transform<Vec3>>,
     pub rotation: Option<Transform<u32>>,
     pub target_translation: Option<Transform<Vec3>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(Camera);
+
 impl ctx::TryFromCtx<'_, Endian> for Camera {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -109,7 +113,6 @@ impl ctx::TryFromCtx<'_, Endian> for Camera {
         }
 
         let mut camera = Camera {
-            inclusive_size,
             name,
             position,
             field_of_view,
@@ -119,28 +122,36 @@ impl ctx::TryFromCtx<'_, Endian> for Camera {
             translation: None,
             rotation: None,
             target_translation: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx).unwrap();
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KCTR_TAG => {
-                    let translation = src.gread_with::<Transform<Vec3>>(offset, ctx)?;
+                    let translation = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     camera.translation = Some(translation);
                 }
                 KCRL_TAG => {
-                    let rotation = src.gread_with::<Transform<u32>>(offset, ctx)?;
+                    let rotation = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     camera.rotation = Some(rotation);
                 }
                 KTTR_TAG => {
-                    let target_translation = src.gread_with::<Transform<Vec3>>(offset, ctx)?;
+                    let target_translation =
+                        read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     camera.target_translation = Some(target_translation);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    camera.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, camera.total_bytes_size())?;
+
         Ok((camera, *offset))
     }
 }
@@ -151,7 +162,7 @@ impl ctx::TryIntoCtx<Endian> for Camera {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
 
         // String has fixed size
         let max_path_len = 80usize;
@@ -185,9 +196,13 @@ impl ctx::TryIntoCtx<Endian> for Camera {
         }
 
         if self.target_translation.is_some() {
-            src.gwrite_with::<u32>(KCRL_TAG, offset, ctx)?;
+            src.gwrite_with::<u32>(KTTR_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<Vec3>>(self.target_translation.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -197,7 +212,8 @@ impl BytesTotalSize for Camera {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         let max_path_len = 80usize;
         result += max_path_len;
@@ -222,6 +238,10 @@ impl BytesTotalSize for Camera {
             result += 4;
             result += self.target_translation.as_ref().unwrap().total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,31 +1,36 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Node, Vec3};
+use crate::error::{check_size, read_at, read_within, MdxError, MdxErrorKind};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct CollisionShapeChunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<CollisionShape>,
 }
 
-calculate_chunk_size_impl!(CollisionShapeChunk);
+chunk_size_impl!(CollisionShapeChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for CollisionShapeChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let collision_shape: CollisionShape = read_within(src, offset, ctx, || {
+                format!("collision shape {}", data.len())
+            })?;
+            total_size += collision_shape.total_bytes_size() as u32;
+            data.push(collision_shape);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((CollisionShapeChunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((CollisionShapeChunk { data }, *offset))
     }
 }
 
@@ -35,9 +40,11 @@ impl ctx::TryIntoCtx<Endian> for CollisionShapeChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for collision_shape in self.data {
+            src.gwrite_with::<CollisionShape>(collision_shape, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -47,8 +54,132 @@ impl BytesTotalSize for CollisionShapeChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for collision_shape in &self.data {
+            result += collision_shape.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Shape {
+    Box { minimum: Vec3, maximum: Vec3 },            // 0
+    Plane { minimum: Vec3, maximum: Vec3 },          // 1
+    Sphere { center: Vec3, radius: f32 },            // 2
+    Cylinder { base: Vec3, top: Vec3, radius: f32 }, // 3
+}
+
+impl Shape {
+    pub fn shape_type(&self) -> u32 {
+        match self {
+            Shape::Box { .. } => 0,
+            Shape::Plane { .. } => 1,
+            Shape::Sphere { .. } => 2,
+            Shape::Cylinder { .. } => 3,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct CollisionShape {
+    pub node: Node,
+    pub shape: Shape,
+}
+
+impl ctx::TryFromCtx<'_, Endian> for CollisionShape {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let shape_type = src.gread_with::<u32>(offset, ctx)?;
+        let shape = match shape_type {
+            0 => Shape::Box {
+                minimum: src.gread_with::<Vec3>(offset, ctx)?,
+                maximum: src.gread_with::<Vec3>(offset, ctx)?,
+            },
+            1 => Shape::Plane {
+                minimum: src.gread_with::<Vec3>(offset, ctx)?,
+                maximum: src.gread_with::<Vec3>(offset, ctx)?,
+            },
+            2 => Shape::Sphere {
+                center: src.gread_with::<Vec3>(offset, ctx)?,
+                radius: src.gread_with::<f32>(offset, ctx)?,
+            },
+            3 => Shape::Cylinder {
+                base: src.gread_with::<Vec3>(offset, ctx)?,
+                top: src.gread_with::<Vec3>(offset, ctx)?,
+                radius: src.gread_with::<f32>(offset, ctx)?,
+            },
+            _ => {
+                return Err(MdxError::new(
+                    *offset - 4,
+                    MdxErrorKind::InvalidValue(format!("Unknown shape type {}", shape_type)),
+                ))
+            }
+        };
+
+        Ok((CollisionShape { node, shape }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for CollisionShape {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        src.gwrite_with::<u32>(self.shape.shape_type(), offset, ctx)?;
+        match self.shape {
+            Shape::Box { minimum, maximum } | Shape::Plane { minimum, maximum } => {
+                src.gwrite_with::<Vec3>(minimum, offset, ctx)?;
+                src.gwrite_with::<Vec3>(maximum, offset, ctx)?;
+            }
+            Shape::Sphere { center, radius } => {
+                src.gwrite_with::<Vec3>(center, offset, ctx)?;
+                src.gwrite_with::<f32>(radius, offset, ctx)?;
+            }
+            Shape::Cylinder { base, top, radius } => {
+                src.gwrite_with::<Vec3>(base, offset, ctx)?;
+                src.gwrite_with::<Vec3>(top, offset, ctx)?;
+                src.gwrite_with::<f32>(radius, offset, ctx)?;
+            }
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for CollisionShape {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        result += self.node.total_bytes_size();
+
+        result += 4; // shape type
+        match &self.shape {
+            Shape::Box { minimum, maximum } | Shape::Plane { minimum, maximum } => {
+                result += minimum.total_bytes_size();
+                result += maximum.total_bytes_size();
+            }
+            Shape::Sphere { center, radius } => {
+                result += center.total_bytes_size();
+                result += size_of_val(radius);
+            }
+            Shape::Cylinder { base, top, radius } => {
+                result += base.total_bytes_size();
+                result += top.total_bytes_size();
+                result += size_of_val(radius);
+            }
+        }
 
         result
     }
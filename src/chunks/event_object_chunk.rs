@@ -1,31 +1,37 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, GlobalSequenceId, Node};
+use crate::consts::KEVT_TAG;
+use crate::error::{check_size, read_at, read_count, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
-use std::mem::size_of_val;
+use std::mem::{size_of, size_of_val};
 
 #[derive(PartialEq, Debug)]
 pub struct EventObjectChunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<EventObject>,
 }
 
-calculate_chunk_size_impl!(EventObjectChunk);
+chunk_size_impl!(EventObjectChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for EventObjectChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let event_object: EventObject =
+                read_within(src, offset, ctx, || format!("event object {}", data.len()))?;
+            total_size += (*offset - start) as u32;
+            data.push(event_object);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((EventObjectChunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((EventObjectChunk { data }, *offset))
     }
 }
 
@@ -35,9 +41,11 @@ impl ctx::TryIntoCtx<Endian> for EventObjectChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for event_object in self.data {
+            src.gwrite_with::<EventObject>(event_object, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -47,8 +55,129 @@ impl BytesTotalSize for EventObjectChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for event_object in &self.data {
+            result += event_object.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum EventKind {
+    Sound,       // SND
+    Footprint,   // FPT
+    SpawnObject, // SPN
+    Splat,       // SPL
+    UberSplat,   // UBR
+}
+
+#[derive(PartialEq, Debug)]
+pub struct EventObject {
+    pub node: Node,
+
+    // KEVT, None when the object has no track at all
+    pub global_sequence_id: Option<GlobalSequenceId>,
+    pub keys: Option<Vec<u32>>,
+}
+
+impl EventObject {
+    // Event names look like SNDxABCD: kind, one spare character, id.
+    pub fn kind(&self) -> Option<EventKind> {
+        match self.node.name.get(0..3)? {
+            "SND" => Some(EventKind::Sound),
+            "FPT" => Some(EventKind::Footprint),
+            "SPN" => Some(EventKind::SpawnObject),
+            "SPL" => Some(EventKind::Splat),
+            "UBR" => Some(EventKind::UberSplat),
+            _ => None,
+        }
+    }
+
+    pub fn event_id(&self) -> Option<&str> {
+        self.node.name.get(4..8)
+    }
+}
+
+impl ctx::TryFromCtx<'_, Endian> for EventObject {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let mut global_sequence_id = None;
+        let mut keys = None;
+
+        // KEVT is optional and the object may end the chunk, so a failed peek means no track
+        if src.pread_with::<u32>(*offset, ctx).ok() == Some(KEVT_TAG) {
+            *offset += 4;
+
+            let keys_count = read_count(src, offset, ctx, size_of::<u32>())?;
+            global_sequence_id = GlobalSequenceId::from_raw(src.gread_with::<u32>(offset, ctx)?);
+            let mut track = Vec::new();
+            for _ in 0..keys_count {
+                let key = src.gread_with::<u32>(offset, ctx)?;
+                track.push(key);
+            }
+            keys = Some(track);
+        }
+
+        Ok((
+            EventObject {
+                node,
+                global_sequence_id,
+                keys,
+            },
+            *offset,
+        ))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for EventObject {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        if let Some(keys) = self.keys {
+            src.gwrite_with::<u32>(KEVT_TAG, offset, ctx)?;
+
+            src.gwrite_with::<u32>(keys.len() as u32, offset, ctx)?;
+            src.gwrite_with::<u32>(
+                GlobalSequenceId::to_raw(self.global_sequence_id),
+                offset,
+                ctx,
+            )?;
+            for key in keys {
+                src.gwrite_with::<u32>(key, offset, ctx)?;
+            }
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for EventObject {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        result += self.node.total_bytes_size();
+
+        if let Some(keys) = &self.keys {
+            result += 4; // KEVT
+            result += 4; // keys count
+            result += 4; // global_sequence_id
+            for key in keys {
+                result += size_of_val(key);
+            }
+        }
 
         result
     }
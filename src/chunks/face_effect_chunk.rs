@@ -0,0 +1,135 @@
+use crate::chunks::BytesTotalSize;
+use crate::error::{check_size, read_within, MdxError};
+use scroll::{ctx, Endian, Pread, Pwrite};
+
+#[derive(PartialEq, Debug)]
+pub struct FaceEffectChunk {
+    // chunk_size / 340
+    pub data: Vec<FaceEffect>,
+}
+
+chunk_size_impl!(FaceEffectChunk);
+
+impl ctx::TryFromCtx<'_, Endian> for FaceEffectChunk {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let chunk_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let mut data = Vec::new();
+        if let Some(face_effect_count) = u32::checked_div(chunk_size, 340) {
+            for _ in 0..face_effect_count {
+                let face_effect: FaceEffect =
+                    read_within(src, offset, ctx, || format!("face effect {}", data.len()))?;
+                data.push(face_effect);
+            }
+        }
+
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((FaceEffectChunk { data }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for FaceEffectChunk {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
+
+        for face_effect in self.data {
+            src.gwrite_with::<FaceEffect>(face_effect, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for FaceEffectChunk {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // chunk_size
+        result += 4;
+
+        for face_effect in &self.data {
+            result += face_effect.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+// Links a facial animation target to its effect file.
+#[derive(PartialEq, Debug, Clone)]
+pub struct FaceEffect {
+    // max length 80
+    pub target: String,
+    // max length 260
+    pub path: String,
+}
+
+impl ctx::TryFromCtx<'_, Endian> for FaceEffect {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &[u8], _ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        // Target has fixed size
+        let max_target_len = 80usize;
+        let target = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_target_len;
+
+        // Path has fixed size
+        let max_path_len = 260usize;
+        let path = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_path_len;
+
+        Ok((FaceEffect { target, path }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for FaceEffect {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        // Target has fixed size
+        let max_target_len = 80usize;
+        let null_offset = &mut offset.clone();
+        for _ in 0..max_target_len {
+            src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+        }
+        src.gwrite_with::<&str>(self.target.as_ref(), &mut offset.clone(), ())?;
+        *offset += max_target_len;
+
+        // Path has fixed size
+        let max_path_len = 260usize;
+        let null_offset = &mut offset.clone();
+        for _ in 0..max_path_len {
+            src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+        }
+        src.gwrite_with::<&str>(self.path.as_ref(), &mut offset.clone(), ())?;
+        *offset += max_path_len;
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for FaceEffect {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        let max_target_len = 80usize;
+        result += max_target_len;
+
+        let max_path_len = 260usize;
+        result += max_path_len;
+
+        result
+    }
+}
@@ -1,19 +1,18 @@
-use crate::chunks::{BytesTotalSize, Color, Transform};
+use crate::chunks::{BytesTotalSize, Color, Transform, UnknownTracks};
 use crate::consts::{KGAC_TAG, KGAO_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct GeosetAnimationChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<GeosetAnimation>,
 }
 
-calculate_chunk_size_impl!(GeosetAnimationChunk);
+chunk_size_impl!(GeosetAnimationChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for GeosetAnimationChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -22,12 +21,17 @@ impl ctx::TryFromCtx<'_, Endian> for GeosetAnimationChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let geoset_animation = src.gread_with::<GeosetAnimation>(offset, ctx)?;
-            total_size += geoset_animation.inclusive_size;
+            let start = *offset;
+            let geoset_animation: GeosetAnimation = read_within(src, offset, ctx, || {
+                format!("geoset animation {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
             data.push(geoset_animation);
         }
 
-        Ok((GeosetAnimationChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((GeosetAnimationChunk { data }, *offset))
     }
 }
 
@@ -37,7 +41,7 @@ impl ctx::TryIntoCtx<Endian> for GeosetAnimationChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for geoset_animation in self.data {
             src.gwrite_with::<GeosetAnimation>(geoset_animation, offset, ctx)?;
@@ -51,7 +55,8 @@ impl BytesTotalSize for GeosetAnimationChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for geoset_animation in &self.data {
             result += geoset_animation.total_bytes_size();
@@ -63,8 +68,6 @@ impl BytesTotalSize for GeosetAnimationChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct GeosetAnimation {
-    pub inclusive_size: u32,
-
     pub alpha: f32,
     pub flags: u32,
     pub color: [f32; 3], // bgr
@@ -72,10 +75,13 @@ pub struct GeosetAnimation {
 
     pub geoset_alpha: Option<Transform<f32>>,
     pub geoset_color: Option<Transform<Color>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(GeosetAnimation);
+
 impl ctx::TryFromCtx<'_, Endian> for GeosetAnimation {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -91,31 +97,37 @@ impl ctx::TryFromCtx<'_, Endian> for GeosetAnimation {
         let geoset_id = src.gread_with::<u32>(offset, ctx)?;
 
         let mut geoset_animation = GeosetAnimation {
-            inclusive_size,
             alpha,
             flags,
             color,
             geoset_id,
             geoset_alpha: None,
             geoset_color: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx)?;
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KGAO_TAG => {
-                    let geoset_alpha = src.gread_with::<Transform<f32>>(offset, ctx)?;
+                    let geoset_alpha = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     geoset_animation.geoset_alpha = Some(geoset_alpha);
                 }
                 KGAC_TAG => {
-                    let geoset_color = src.gread_with::<Transform<Color>>(offset, ctx)?;
+                    let geoset_color = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     geoset_animation.geoset_color = Some(geoset_color);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    geoset_animation.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, *offset)?;
+
         Ok((geoset_animation, *offset))
     }
 }
@@ -126,7 +138,7 @@ impl ctx::TryIntoCtx<Endian> for GeosetAnimation {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
         src.gwrite_with::<f32>(self.alpha, offset, ctx)?;
         src.gwrite_with::<u32>(self.flags, offset, ctx)?;
         for id in 0..3 {
@@ -142,6 +154,10 @@ impl ctx::TryIntoCtx<Endian> for GeosetAnimation {
             src.gwrite_with::<u32>(KGAC_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<Color>>(self.geoset_color.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -151,7 +167,8 @@ impl BytesTotalSize for GeosetAnimation {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
         result += size_of_val(&self.alpha);
         result += size_of_val(&self.flags);
         result += size_of_val(&self.color);
@@ -165,6 +182,10 @@ impl BytesTotalSize for GeosetAnimation {
             result += 4;
             result += self.geoset_color.as_ref().unwrap().total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,25 +1,28 @@
 use crate::chunks::BytesTotalSize;
-use crate::chunks::Extent;
+use crate::chunks::{Extent, Vec4};
 use crate::consts::{
-    GNDX_TAG, MATS_TAG, MTGC_TAG, NRMS_TAG, PCNT_TAG, PTYP_TAG, PVTX_TAG, UVAS_TAG, UVBS_TAG,
-    VRTX_TAG,
+    GNDX_TAG, MATS_TAG, MTGC_TAG, NRMS_TAG, PCNT_TAG, PTYP_TAG, PVTX_TAG, SKIN_TAG, TANG_TAG,
+    UVAS_TAG, UVBS_TAG, VRTX_TAG,
 };
-use scroll::{ctx, Endian, Error, Pread, Pwrite};
-use std::mem::size_of_val;
+use crate::error::{check_size, read_at, read_count, read_within, FourCC, MdxError, MdxErrorKind};
+use scroll::{ctx, Endian, Pread, Pwrite};
+use std::mem::{size_of, size_of_val};
 
 #[derive(PartialEq, Debug)]
 pub struct GeosetChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Geoset>,
 }
 
-calculate_chunk_size_impl!(GeosetChunk);
+chunk_size_impl!(GeosetChunk);
 
-impl ctx::TryFromCtx<'_, Endian> for GeosetChunk {
-    type Error = scroll::Error;
+// Layout of geosets depends on the model version, so it comes with the context.
+impl ctx::TryFromCtx<'_, (Endian, u32)> for GeosetChunk {
+    type Error = MdxError;
 
-    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+    fn try_from_ctx(
+        src: &[u8],
+        (ctx, version): (Endian, u32),
+    ) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
@@ -27,15 +30,19 @@ impl ctx::TryFromCtx<'_, Endian> for GeosetChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let geoset = src.gread_with::<Geoset>(offset, ctx)?;
+            let start = *offset;
+            let geoset: Geoset = read_within(src, offset, (ctx, version), || {
+                format!("geoset {}", data.len())
+            })?;
             //total_size += bone.node.inclusive_size + 4 + 4;
             //total_size += geoset.total_bytes_size() as u32;
-            total_size += geoset.inclusive_size;
-            assert_eq!(geoset.inclusive_size, geoset.total_bytes_size() as u32);
+            total_size += (*offset - start) as u32;
             data.push(geoset);
         }
 
-        Ok((GeosetChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((GeosetChunk { data }, *offset))
     }
 }
 
@@ -45,7 +52,7 @@ impl ctx::TryIntoCtx<Endian> for GeosetChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for geoset in self.data {
             src.gwrite_with::<Geoset>(geoset, offset, ctx)?;
@@ -55,11 +62,35 @@ impl ctx::TryIntoCtx<Endian> for GeosetChunk {
     }
 }
 
+impl GeosetChunk {
+    // Adds defaults for or drops the v900+ fields, so the layout matches the version. Tangents and
+    // skin weights are never dropped, a geoset still having them can't go below version 900.
+    pub(crate) fn match_version(&mut self, version: u32) -> Result<(), scroll::Error> {
+        for (i, geoset) in self.data.iter_mut().enumerate() {
+            if version > 800 {
+                geoset.lod.get_or_insert_with(|| GeosetLod {
+                    lod: 0,
+                    lod_name: String::new(),
+                });
+            } else if geoset.tangents.is_some() || geoset.skin.is_some() {
+                return Err(scroll::Error::Custom(format!(
+                    "geoset {} has tangents or skin weights, which need version 900 or later",
+                    i
+                )));
+            } else {
+                geoset.lod = None;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl BytesTotalSize for GeosetChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for geoset in &self.data {
             result += geoset.total_bytes_size();
@@ -71,151 +102,144 @@ impl BytesTotalSize for GeosetChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct Geoset {
-    pub inclusive_size: u32,
-
-    pub vertex_count: u32, // VRTX
-    pub vertex_positions: Vec<VertexPosition>,
-
-    pub normal_count: u32, // NRMS
-    pub vertex_normals: Vec<VertexNormal>,
-
-    pub face_type_groups_count: u32, // PTYP
-    pub face_type_groups: Vec<FaceTypeGroup>,
-
-    pub face_groups_count: u32, // PCNT
-    pub face_groups: Vec<FaceGroup>,
-
-    pub faces_count: u32, // PVTX
-    pub faces: Vec<Face>,
-
-    pub vertex_groups_count: u32, // GNDX
-    pub vertex_groups: Vec<VertexGroup>,
-
-    pub matrix_groups_count: u32, // MTGC
-    pub matrix_groups: Vec<MatrixGroup>,
-
-    pub matrix_indexes_count: u32, // MATS
-    pub matrix_indexes: Vec<MatrixIndex>,
+    pub vertex_positions: Vec<VertexPosition>, // VRTX
+    pub vertex_normals: Vec<VertexNormal>,     // NRMS
+    pub face_type_groups: Vec<FaceTypeGroup>,  // PTYP
+    pub face_groups: Vec<FaceGroup>,           // PCNT
+    pub faces: Vec<Face>,                      // PVTX
+    pub vertex_groups: Vec<VertexGroup>,       // GNDX
+    pub matrix_groups: Vec<MatrixGroup>,       // MTGC
+    pub matrix_indexes: Vec<MatrixIndex>,      // MATS
 
     pub material_id: u32,
     pub selection_group: u32,
-    pub selection_type: u32, // 0 - None | 4 - Unselectable
+    pub selection_type: u32,    // 0 - None | 4 - Unselectable
+    pub lod: Option<GeosetLod>, // version 900+
 
     pub extent: Extent,
-    pub extents_count: u32,
     pub extent_sequences: Vec<Extent>,
 
-    pub texture_coordinate_sets_count: u32, // UVAS - UVBS inside
-    pub texture_coordinate_sets: Vec<TextureCoordinateSet>,
+    pub tangents: Option<Vec<Vec4>>, // TANG - optional in version 900+
+    pub skin: Option<Vec<u8>>,       // SKIN - optional in version 900+
+
+    pub texture_coordinate_sets: Vec<TextureCoordinateSet>, // UVAS - UVBS inside
 }
 
-impl ctx::TryFromCtx<'_, Endian> for Geoset {
-    type Error = scroll::Error;
+inclusive_size_impl!(Geoset);
 
-    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+impl ctx::TryFromCtx<'_, (Endian, u32)> for Geoset {
+    type Error = MdxError;
+
+    fn try_from_ctx(
+        src: &[u8],
+        (ctx, version): (Endian, u32),
+    ) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let check_for_tag = |offset: &mut usize, expect_tag: &str| -> Result<_, Self::Error> {
-            info!("TagOffset: {}", &offset);
-            let mut tag_offset = offset.clone();
-            let tag_buffer = (0..4)
-                .map(|_| src.gread::<u8>(&mut tag_offset).unwrap())
-                .collect::<Vec<u8>>();
-            let tag_name = String::from_utf8(tag_buffer).unwrap_or("NOTAG".to_string());
-            let tag = src.gread_with::<u32>(offset, ctx).unwrap();
-
-            info!("TagHex: {}", format!("{:X}", &tag));
-            info!("TagDec: {}", &tag);
-            info!("TagName: {}", &tag_name);
-
-            if tag_name != expect_tag {
-                return Err(Error::Custom(format!(
-                    "Geoset format is not correct. Expected {} - Found {}",
-                    expect_tag, tag_name
-                )));
+        let check_for_tag = |offset: &mut usize, expected: u32| -> Result<(), MdxError> {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+            info!("Tag: {}", FourCC(tag));
+
+            if tag != expected {
+                return Err(MdxError::new(
+                    *offset - 4,
+                    MdxErrorKind::UnexpectedTag {
+                        expected: FourCC(expected),
+                        found: FourCC(tag),
+                    },
+                ));
             }
             Ok(())
         };
 
         // VRTX
-        check_for_tag(offset, "VRTX")?;
+        check_for_tag(offset, VRTX_TAG)?;
 
-        let vertex_count = src.gread_with::<u32>(offset, ctx)?;
+        let vertex_count = read_count(src, offset, ctx, size_of::<VertexPosition>())?;
         let mut vertex_positions = Vec::new();
         for _ in 0..vertex_count {
-            let value = src.gread_with::<VertexPosition>(offset, ctx)?;
+            let value: VertexPosition = read_within(src, offset, ctx, || "VRTX".to_string())?;
             vertex_positions.push(value);
         }
 
         // NRMS
-        check_for_tag(offset, "NRMS")?;
+        check_for_tag(offset, NRMS_TAG)?;
 
-        let normal_count = src.gread_with::<u32>(offset, ctx)?;
+        let normal_count = read_count(src, offset, ctx, size_of::<VertexNormal>())?;
         let mut vertex_normals = Vec::new();
         for _ in 0..normal_count {
-            let value = src.gread_with::<VertexNormal>(offset, ctx)?;
+            let value: VertexNormal = read_within(src, offset, ctx, || "NRMS".to_string())?;
             vertex_normals.push(value);
         }
 
         // PTYP
-        check_for_tag(offset, "PTYP")?;
+        check_for_tag(offset, PTYP_TAG)?;
 
-        let face_type_groups_count = src.gread_with::<u32>(offset, ctx)?;
+        let face_type_groups_count = read_count(src, offset, ctx, size_of::<FaceTypeGroup>())?;
         let mut face_type_groups = Vec::new();
         for _ in 0..face_type_groups_count {
-            let value = src.gread_with::<FaceTypeGroup>(offset, ctx)?;
+            let value: FaceTypeGroup = read_within(src, offset, ctx, || "PTYP".to_string())?;
             face_type_groups.push(value);
         }
 
         // PCNT
-        check_for_tag(offset, "PCNT")?;
+        check_for_tag(offset, PCNT_TAG)?;
 
-        let face_groups_count = src.gread_with::<u32>(offset, ctx)?;
+        let face_groups_count = read_count(src, offset, ctx, size_of::<FaceGroup>())?;
         let mut face_groups = Vec::new();
         for _ in 0..face_groups_count {
-            let value = src.gread_with::<FaceGroup>(offset, ctx)?;
+            let value: FaceGroup = read_within(src, offset, ctx, || "PCNT".to_string())?;
             face_groups.push(value);
         }
 
         // PVTX
-        check_for_tag(offset, "PVTX")?;
-
-        let faces_count = src.gread_with::<u32>(offset, ctx)?;
+        check_for_tag(offset, PVTX_TAG)?;
+
+        let faces_count = read_count(src, offset, ctx, size_of::<u16>())?;
+        if faces_count % 3 != 0 {
+            return Err(MdxError::new(
+                *offset - 4,
+                MdxErrorKind::InvalidValue(format!(
+                    "faces count {} is not a multiple of 3",
+                    faces_count
+                )),
+            ));
+        }
         let mut faces = Vec::new();
         for _ in 0..faces_count / 3 {
-            let value = src.gread_with::<Face>(offset, ctx)?;
+            let value: Face = read_within(src, offset, ctx, || "PVTX".to_string())?;
             faces.push(value);
         }
 
         // GNDX
-        check_for_tag(offset, "GNDX")?;
+        check_for_tag(offset, GNDX_TAG)?;
 
-        let vertex_groups_count = src.gread_with::<u32>(offset, ctx)?;
+        let vertex_groups_count = read_count(src, offset, ctx, size_of::<VertexGroup>())?;
         let mut vertex_groups = Vec::new();
         for _ in 0..vertex_groups_count {
-            let value = src.gread_with::<VertexGroup>(offset, ctx)?;
+            let value: VertexGroup = read_within(src, offset, ctx, || "GNDX".to_string())?;
             vertex_groups.push(value);
         }
 
         // MTGC
-        check_for_tag(offset, "MTGC")?;
+        check_for_tag(offset, MTGC_TAG)?;
 
-        let matrix_groups_count = src.gread_with::<u32>(offset, ctx)?;
+        let matrix_groups_count = read_count(src, offset, ctx, size_of::<MatrixGroup>())?;
         let mut matrix_groups = Vec::new();
         for _ in 0..matrix_groups_count {
-            let value = src.gread_with::<MatrixGroup>(offset, ctx)?;
+            let value: MatrixGroup = read_within(src, offset, ctx, || "MTGC".to_string())?;
             matrix_groups.push(value);
         }
 
         // MATS
-        check_for_tag(offset, "MATS")?;
+        check_for_tag(offset, MATS_TAG)?;
 
-        let matrix_indexes_count = src.gread_with::<u32>(offset, ctx)?;
+        let matrix_indexes_count = read_count(src, offset, ctx, size_of::<MatrixIndex>())?;
         let mut matrix_indexes = Vec::new();
         for _ in 0..matrix_indexes_count {
-            let value = src.gread_with::<MatrixIndex>(offset, ctx)?;
+            let value: MatrixIndex = read_within(src, offset, ctx, || "MATS".to_string())?;
             matrix_indexes.push(value);
         }
 
@@ -224,56 +248,82 @@ impl ctx::TryFromCtx<'_, Endian> for Geoset {
         let selection_group = src.gread_with::<u32>(offset, ctx)?;
         let selection_type = src.gread_with::<u32>(offset, ctx)?;
 
+        let mut lod = None;
+        if version > 800 {
+            let value: GeosetLod = read_within(src, offset, ctx, || "LOD".to_string())?;
+            lod = Some(value);
+        }
+
         let extent = src.gread_with::<Extent>(offset, ctx)?;
-        let extents_count = src.gread_with::<u32>(offset, ctx)?;
+        let extents_count = read_count(src, offset, ctx, size_of::<Extent>())?;
         let mut extent_sequences = Vec::new();
         for _ in 0..extents_count {
             let value = src.gread_with::<Extent>(offset, ctx)?;
             extent_sequences.push(value);
         }
 
+        let peek_tag = |offset: &mut usize| src.pread_with::<u32>(*offset, ctx).ok();
+
+        // TANG
+        let mut tangents = None;
+        if version > 800 && peek_tag(offset) == Some(TANG_TAG) {
+            *offset += 4;
+            let tangents_count = read_count(src, offset, ctx, size_of::<Vec4>())?;
+            let mut values = Vec::new();
+            for _ in 0..tangents_count {
+                let value: Vec4 = read_within(src, offset, ctx, || "TANG".to_string())?;
+                values.push(value);
+            }
+            tangents = Some(values);
+        }
+
+        // SKIN
+        let mut skin = None;
+        if version > 800 && peek_tag(offset) == Some(SKIN_TAG) {
+            *offset += 4;
+            let skin_count = read_count(src, offset, ctx, 1)?;
+            let values: &[u8] =
+                read_within(src, offset, skin_count as usize, || "SKIN".to_string())?;
+            skin = Some(values.to_vec());
+        }
+
         // UVAS | UVBS
-        check_for_tag(offset, "UVAS")?;
+        check_for_tag(offset, UVAS_TAG)?;
 
-        let texture_coordinate_sets_count = src.gread_with::<u32>(offset, ctx)?;
+        // UVBS tag and count at least
+        let texture_coordinate_sets_count = read_count(src, offset, ctx, 4 + 4)?;
         let mut texture_coordinate_sets = Vec::new();
-        for _ in 0..texture_coordinate_sets_count {
-            check_for_tag(offset, "UVBS")?;
+        for i in 0..texture_coordinate_sets_count {
+            check_for_tag(offset, UVBS_TAG)?;
 
-            let value = src.gread_with::<TextureCoordinateSet>(offset, ctx)?;
+            let value: TextureCoordinateSet =
+                read_within(src, offset, ctx, || format!("UVBS {}", i))?;
             texture_coordinate_sets.push(value);
         }
 
-        Ok((
-            Geoset {
-                inclusive_size,
-                vertex_count,
-                vertex_positions,
-                normal_count,
-                vertex_normals,
-                face_type_groups_count,
-                face_type_groups,
-                face_groups_count,
-                face_groups,
-                faces_count,
-                faces,
-                vertex_groups_count,
-                vertex_groups,
-                matrix_groups_count,
-                matrix_groups,
-                matrix_indexes_count,
-                matrix_indexes,
-                material_id,
-                selection_group,
-                selection_type,
-                extent,
-                extents_count,
-                extent_sequences,
-                texture_coordinate_sets_count,
-                texture_coordinate_sets,
-            },
-            *offset,
-        ))
+        let geoset = Geoset {
+            vertex_positions,
+            vertex_normals,
+            face_type_groups,
+            face_groups,
+            faces,
+            vertex_groups,
+            matrix_groups,
+            matrix_indexes,
+            material_id,
+            selection_group,
+            selection_type,
+            lod,
+            extent,
+            extent_sequences,
+            tangents,
+            skin,
+            texture_coordinate_sets,
+        };
+
+        check_size(0, inclusive_size, geoset.total_bytes_size())?;
+
+        Ok((geoset, *offset))
     }
 }
 
@@ -283,12 +333,12 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
 
         // VRTX
         src.gwrite_with::<u32>(VRTX_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.vertex_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.vertex_positions.len() as u32, offset, ctx)?;
         for value in self.vertex_positions {
             src.gwrite_with::<VertexPosition>(value, offset, ctx)?;
         }
@@ -296,7 +346,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // NRMS
         src.gwrite_with::<u32>(NRMS_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.normal_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.vertex_normals.len() as u32, offset, ctx)?;
         for value in self.vertex_normals {
             src.gwrite_with::<VertexNormal>(value, offset, ctx)?;
         }
@@ -304,7 +354,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // PTYP
         src.gwrite_with::<u32>(PTYP_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.face_type_groups_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.face_type_groups.len() as u32, offset, ctx)?;
         for value in self.face_type_groups {
             src.gwrite_with::<FaceTypeGroup>(value, offset, ctx)?;
         }
@@ -312,7 +362,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // PCNT
         src.gwrite_with::<u32>(PCNT_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.face_groups_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.face_groups.len() as u32, offset, ctx)?;
         for value in self.face_groups {
             src.gwrite_with::<FaceGroup>(value, offset, ctx)?;
         }
@@ -320,9 +370,8 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // PVTX
         src.gwrite_with::<u32>(PVTX_TAG, offset, ctx)?;
 
-        // DONT FORGET TRICK HERE.
-        // faces_count should be always faces.len() * 3 when writing, and faces_count / 3 by reading
-        src.gwrite_with::<u32>(self.faces_count, offset, ctx)?;
+        // Count of indexes, three per face
+        src.gwrite_with::<u32>(self.faces.len() as u32 * 3, offset, ctx)?;
         for value in self.faces {
             src.gwrite_with::<Face>(value, offset, ctx)?;
         }
@@ -330,7 +379,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // GNDX
         src.gwrite_with::<u32>(GNDX_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.vertex_groups_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.vertex_groups.len() as u32, offset, ctx)?;
         for value in self.vertex_groups {
             src.gwrite_with::<VertexGroup>(value, offset, ctx)?;
         }
@@ -338,7 +387,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // MTGC
         src.gwrite_with::<u32>(MTGC_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.matrix_groups_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.matrix_groups.len() as u32, offset, ctx)?;
         for value in self.matrix_groups {
             src.gwrite_with::<MatrixGroup>(value, offset, ctx)?;
         }
@@ -346,7 +395,7 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         // MATS
         src.gwrite_with::<u32>(MATS_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.matrix_indexes_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.matrix_indexes.len() as u32, offset, ctx)?;
         for value in self.matrix_indexes {
             src.gwrite_with::<MatrixIndex>(value, offset, ctx)?;
         }
@@ -356,17 +405,41 @@ impl ctx::TryIntoCtx<Endian> for Geoset {
         src.gwrite_with::<u32>(self.selection_group, offset, ctx)?;
         src.gwrite_with::<u32>(self.selection_type, offset, ctx)?;
 
+        if let Some(lod) = self.lod {
+            src.gwrite_with::<GeosetLod>(lod, offset, ctx)?;
+        }
+
         // Extents
         src.gwrite_with::<Extent>(self.extent, offset, ctx)?;
-        src.gwrite_with::<u32>(self.extents_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.extent_sequences.len() as u32, offset, ctx)?;
         for value in self.extent_sequences {
             src.gwrite_with::<Extent>(value, offset, ctx)?;
         }
 
+        // TANG
+        if let Some(tangents) = self.tangents {
+            src.gwrite_with::<u32>(TANG_TAG, offset, ctx)?;
+
+            src.gwrite_with::<u32>(tangents.len() as u32, offset, ctx)?;
+            for value in tangents {
+                src.gwrite_with::<Vec4>(value, offset, ctx)?;
+            }
+        }
+
+        // SKIN
+        if let Some(skin) = self.skin {
+            src.gwrite_with::<u32>(SKIN_TAG, offset, ctx)?;
+
+            src.gwrite_with::<u32>(skin.len() as u32, offset, ctx)?;
+            for value in skin {
+                src.gwrite_with::<u8>(value, offset, ctx)?;
+            }
+        }
+
         // UVAS | UVBS
         src.gwrite_with::<u32>(UVAS_TAG, offset, ctx)?;
 
-        src.gwrite_with::<u32>(self.texture_coordinate_sets_count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.texture_coordinate_sets.len() as u32, offset, ctx)?;
         for value in self.texture_coordinate_sets {
             src.gwrite_with::<u32>(UVBS_TAG, offset, ctx)?;
             src.gwrite_with::<TextureCoordinateSet>(value, offset, ctx)?;
@@ -380,52 +453,53 @@ impl BytesTotalSize for Geoset {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         result += 4; // VRTX
-        result += size_of_val(&self.vertex_count);
+        result += 4; // count
         for vp in &self.vertex_positions {
             result += vp.total_bytes_size();
         }
 
         result += 4; // NRMS
-        result += size_of_val(&self.normal_count);
+        result += 4; // count
         for vn in &self.vertex_normals {
             result += vn.total_bytes_size();
         }
 
         result += 4; // PTYP
-        result += size_of_val(&self.face_type_groups_count);
+        result += 4; // count
         for ftg in &self.face_type_groups {
             result += ftg.total_bytes_size();
         }
 
         result += 4; // PCNT
-        result += size_of_val(&self.face_groups_count);
+        result += 4; // count
         for fg in &self.face_groups {
             result += fg.total_bytes_size();
         }
 
         result += 4; // PVTX
-        result += size_of_val(&self.faces_count);
+        result += 4; // count
         for f in &self.faces {
             result += f.total_bytes_size();
         }
 
         result += 4; // GNDX
-        result += size_of_val(&self.vertex_groups_count);
+        result += 4; // count
         for vg in &self.vertex_groups {
             result += vg.total_bytes_size();
         }
 
         result += 4; // MTGC
-        result += size_of_val(&self.matrix_groups_count);
+        result += 4; // count
         for mg in &self.matrix_groups {
             result += mg.total_bytes_size();
         }
 
         result += 4; // MATS
-        result += size_of_val(&self.matrix_indexes_count);
+        result += 4; // count
         for mi in &self.matrix_indexes {
             result += mi.total_bytes_size();
         }
@@ -433,15 +507,31 @@ impl BytesTotalSize for Geoset {
         result += size_of_val(&self.material_id);
         result += size_of_val(&self.selection_group);
         result += size_of_val(&self.selection_type);
+        if let Some(lod) = self.lod.as_ref() {
+            result += lod.total_bytes_size();
+        }
 
         result += size_of_val(&self.extent);
-        result += size_of_val(&self.extents_count);
+        result += 4; // count
         for extent in &self.extent_sequences {
             result += extent.total_bytes_size();
         }
 
+        if let Some(tangents) = self.tangents.as_ref() {
+            result += 4; // TANG
+            result += 4; // count
+            for tangent in tangents {
+                result += tangent.total_bytes_size();
+            }
+        }
+        if let Some(skin) = self.skin.as_ref() {
+            result += 4; // SKIN
+            result += 4; // count
+            result += skin.len();
+        }
+
         result += 4; // UVAS
-        result += size_of_val(&self.texture_coordinate_sets_count);
+        result += 4; // count
         for tcs in &self.texture_coordinate_sets {
             result += 4; // UVBS
             result += tcs.total_bytes_size();
@@ -451,6 +541,65 @@ impl BytesTotalSize for Geoset {
     }
 }
 
+// Level of detail of a geoset, version 900+.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GeosetLod {
+    pub lod: u32,
+    // max length 80
+    pub lod_name: String,
+}
+
+impl ctx::TryFromCtx<'_, Endian> for GeosetLod {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let lod = src.gread_with::<u32>(offset, ctx)?;
+
+        // Name has fixed size
+        let max_name_len = 80usize;
+        let lod_name = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_name_len;
+
+        Ok((GeosetLod { lod, lod_name }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for GeosetLod {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.lod, offset, ctx)?;
+
+        // Name has fixed size
+        let max_name_len = 80usize;
+        let null_offset = &mut offset.clone();
+        for _ in 0..max_name_len {
+            src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+        }
+        src.gwrite_with::<&str>(self.lod_name.as_ref(), &mut offset.clone(), ())?;
+        *offset += max_name_len;
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for GeosetLod {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        result += size_of_val(&self.lod);
+
+        let max_name_len = 80usize;
+        result += max_name_len;
+
+        result
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct VertexPosition {
     pub position: [f32; 3],
@@ -792,7 +941,6 @@ impl BytesTotalSize for MatrixIndex {
 
 #[derive(PartialEq, Debug)]
 pub struct TextureCoordinateSet {
-    pub count: u32, // UVBS
     pub texture_coordinates: Vec<[f32; 2]>,
 }
 
@@ -802,9 +950,9 @@ impl ctx::TryFromCtx<'_, Endian> for TextureCoordinateSet {
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
-        let count = src.gread_with::<u32>(offset, ctx)?;
+        let count = read_count(src, offset, ctx, size_of::<[f32; 2]>())?;
 
-        let mut texture_coordinates = Vec::with_capacity(count as usize);
+        let mut texture_coordinates = Vec::new();
 
         for _ in 0..count {
             let texture_coordinate = [
@@ -813,12 +961,9 @@ impl ctx::TryFromCtx<'_, Endian> for TextureCoordinateSet {
             ];
             texture_coordinates.push(texture_coordinate);
         }
-        assert_eq!(count as usize, texture_coordinates.len());
-        assert_eq!(texture_coordinates.capacity(), texture_coordinates.len());
 
         Ok((
             TextureCoordinateSet {
-                count,
                 texture_coordinates,
             },
             *offset,
@@ -832,7 +977,7 @@ impl ctx::TryIntoCtx<Endian> for TextureCoordinateSet {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.count, offset, ctx)?;
+        src.gwrite_with::<u32>(self.texture_coordinates.len() as u32, offset, ctx)?;
 
         for tc in self.texture_coordinates {
             src.gwrite_with::<f32>(tc[0], offset, ctx)?;
@@ -847,7 +992,8 @@ impl BytesTotalSize for TextureCoordinateSet {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.count);
+        // count
+        result += 4;
         for tc in &self.texture_coordinates {
             result += size_of_val(tc);
         }
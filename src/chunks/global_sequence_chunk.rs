@@ -1,19 +1,18 @@
 use crate::chunks::BytesTotalSize;
+use crate::error::{check_size, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct GlobalSequenceChunk {
-    pub chunk_size: u32,
-
     // chunk_size / 4
     pub data: Vec<GlobalSequence>,
 }
 
-calculate_chunk_size_impl!(GlobalSequenceChunk);
+chunk_size_impl!(GlobalSequenceChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for GlobalSequenceChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -22,12 +21,15 @@ impl ctx::TryFromCtx<'_, Endian> for GlobalSequenceChunk {
         let mut data = Vec::new();
         if let Some(sequence_count) = u32::checked_div(chunk_size.clone(), 4) {
             for _ in 0..sequence_count {
-                let sequence = src.gread_with::<GlobalSequence>(offset, ctx)?;
+                let sequence: GlobalSequence =
+                    read_within(src, offset, ctx, || format!("sequence {}", data.len()))?;
                 data.push(sequence);
             }
         }
 
-        Ok((GlobalSequenceChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((GlobalSequenceChunk { data }, *offset))
     }
 }
 
@@ -37,7 +39,7 @@ impl ctx::TryIntoCtx<Endian> for GlobalSequenceChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for global_sequence in self.data {
             src.gwrite_with::<GlobalSequence>(global_sequence, offset, ctx)?;
@@ -51,7 +53,8 @@ impl BytesTotalSize for GlobalSequenceChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for global_sequence in &self.data {
             result += global_sequence.total_bytes_size();
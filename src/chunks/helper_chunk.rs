@@ -1,18 +1,16 @@
 use crate::chunks::{BytesTotalSize, Node};
+use crate::error::{check_size, read_at, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
-use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct HelperChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Helper>,
 }
 
-calculate_chunk_size_impl!(HelperChunk);
+chunk_size_impl!(HelperChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for HelperChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -21,12 +19,16 @@ impl ctx::TryFromCtx<'_, Endian> for HelperChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let helper = src.gread_with::<Helper>(offset, ctx)?;
-            total_size += helper.node.inclusive_size;
+            let start = *offset;
+            let helper: Helper =
+                read_within(src, offset, ctx, || format!("helper {}", data.len()))?;
+            total_size += (*offset - start) as u32;
             data.push(helper);
         }
 
-        Ok((HelperChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((HelperChunk { data }, *offset))
     }
 }
 
@@ -36,7 +38,7 @@ impl ctx::TryIntoCtx<Endian> for HelperChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for helper in self.data {
             src.gwrite_with::<Helper>(helper, offset, ctx)?;
@@ -50,7 +52,8 @@ impl BytesTotalSize for HelperChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for helper in &self.data {
             result += helper.total_bytes_size();
@@ -66,11 +69,11 @@ pub struct Helper {
 }
 
 impl ctx::TryFromCtx<'_, Endian> for Helper {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
-        let node = src.gread_with::<Node>(offset, ctx)?;
+        let node: Node = read_at(src, offset, ctx)?;
 
         Ok((Helper { node }, *offset))
     }
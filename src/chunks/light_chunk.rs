@@ -1,5 +1,6 @@
-use crate::chunks::{BytesTotalSize, Color, Node, Transform};
+use crate::chunks::{BytesTotalSize, Color, Node, Transform, UnknownTracks};
 use crate::consts::{KLAC_TAG, KLAE_TAG, KLAI_TAG, KLAS_TAG, KLAV_TAG, KLBC_TAG, KLBI_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
@@ -7,15 +8,13 @@ use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct LightChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<Light>,
 }
 
-calculate_chunk_size_impl!(LightChunk);
+chunk_size_impl!(LightChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for LightChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -25,12 +24,15 @@ impl ctx::TryFromCtx<'_, Endian> for LightChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let light = src.gread_with::<Light>(offset, ctx)?;
-            total_size += light.inclusive_size;
+            let start = *offset;
+            let light: Light = read_within(src, offset, ctx, || format!("light {}", data.len()))?;
+            total_size += (*offset - start) as u32;
             data.push(light);
         }
 
-        Ok((LightChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((LightChunk { data }, *offset))
     }
 }
 
@@ -40,7 +42,7 @@ impl ctx::TryIntoCtx<Endian> for LightChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for light in self.data {
             src.gwrite_with::<Light>(light, offset, ctx)?;
@@ -54,7 +56,8 @@ impl BytesTotalSize for LightChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for light in &self.data {
             result += light.total_bytes_size();
@@ -66,8 +69,6 @@ impl BytesTotalSize for LightChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct Light {
-    pub inclusive_size: u32,
-
     pub node: Node,
 
     pub light_type: u32, // 0: omni 1: directional 2: ambient
@@ -87,17 +88,20 @@ pub struct Light {
     pub intensity_transform: Option<Transform<f32>>,
     pub ambient_intensity_transform: Option<Transform<f32>>,
     pub visibility_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(Light);
+
 impl ctx::TryFromCtx<'_, Endian> for Light {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let node = src.gread_with::<Node>(offset, ctx)?;
+        let node: Node = read_at(src, offset, ctx)?;
 
         let light_type = src.gread_with::<u32>(offset, ctx)?;
         let attenuation_start = src.gread_with::<f32>(offset, ctx)?;
@@ -108,7 +112,6 @@ impl ctx::TryFromCtx<'_, Endian> for Light {
         let ambient_intensity = src.gread_with::<f32>(offset, ctx)?;
 
         let mut light = Light {
-            inclusive_size,
             node,
             light_type,
             attenuation_start,
@@ -124,44 +127,51 @@ impl ctx::TryFromCtx<'_, Endian> for Light {
             intensity_transform: None,
             ambient_intensity_transform: None,
             visibility_transform: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx)?;
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KLAS_TAG => {
-                    let ktas = src.gread_with(offset, ctx)?;
+                    let ktas = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.attenuation_start_transform = Some(ktas);
                 }
                 KLAE_TAG => {
-                    let ktae = src.gread_with(offset, ctx)?;
+                    let ktae = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.attenuation_end_transform = Some(ktae);
                 }
                 KLAC_TAG => {
-                    let klac = src.gread_with(offset, ctx)?;
+                    let klac = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.color_transform = Some(klac);
                 }
                 KLAI_TAG => {
-                    let klai = src.gread_with(offset, ctx)?;
+                    let klai = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.intensity_transform = Some(klai);
                 }
                 KLBC_TAG => {
-                    let klbc = src.gread_with(offset, ctx)?;
+                    let klbc = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.ambient_color_transform = Some(klbc);
                 }
                 KLBI_TAG => {
-                    let klbi = src.gread_with(offset, ctx)?;
+                    let klbi = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.ambient_intensity_transform = Some(klbi);
                 }
                 KLAV_TAG => {
-                    let klav = src.gread_with(offset, ctx)?;
+                    let klav = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     light.visibility_transform = Some(klav);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    light.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, *offset)?;
+
         Ok((light, *offset))
     }
 }
@@ -172,7 +182,7 @@ impl ctx::TryIntoCtx<Endian> for Light {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
 
         src.gwrite_with::<Node>(self.node, offset, ctx)?;
 
@@ -228,6 +238,10 @@ impl ctx::TryIntoCtx<Endian> for Light {
             src.gwrite_with::<u32>(KLAV_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<f32>>(self.visibility_transform.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -237,7 +251,8 @@ impl BytesTotalSize for Light {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         result += self.node.total_bytes_size();
 
@@ -301,6 +316,10 @@ impl BytesTotalSize for Light {
                 .unwrap()
                 .total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,31 +1,42 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Transform, UnknownTracks, Vec3};
+use crate::consts::{KFC3_TAG, KFCA_TAG, KFTC_TAG, KMTA_TAG, KMTE_TAG, KMTF_TAG, LAYS_TAG};
+use crate::error::{check_size, read_at, read_count, read_within, FourCC, MdxError, MdxErrorKind};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct MaterialChunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<Material>,
 }
 
-calculate_chunk_size_impl!(MaterialChunk);
+chunk_size_impl!(MaterialChunk);
 
-impl ctx::TryFromCtx<'_, Endian> for MaterialChunk {
-    type Error = scroll::Error;
+// Layout of materials depends on the model version, so it comes with the context.
+impl ctx::TryFromCtx<'_, (Endian, u32)> for MaterialChunk {
+    type Error = MdxError;
 
-    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+    fn try_from_ctx(
+        src: &[u8],
+        (ctx, version): (Endian, u32),
+    ) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let material: Material = read_within(src, offset, (ctx, version), || {
+                format!("material {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
+            data.push(material);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((MaterialChunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((MaterialChunk { data }, *offset))
     }
 }
 
@@ -35,20 +46,482 @@ impl ctx::TryIntoCtx<Endian> for MaterialChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for material in self.data {
+            src.gwrite_with::<Material>(material, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
 }
 
+impl MaterialChunk {
+    // Adds defaults for or drops the HD fields, so the layout matches the version.
+    // Raw bytes of version 1100+ layers can't be made up or converted, so those fail.
+    pub(crate) fn match_version(&mut self, version: u32) -> Result<(), scroll::Error> {
+        for (i, material) in self.data.iter_mut().enumerate() {
+            if version > 800 {
+                material.shader.get_or_insert_with(String::new);
+            } else {
+                material.shader = None;
+            }
+
+            for (j, layer) in material.layers.iter_mut().enumerate() {
+                if (version > 1000) != layer.unknown_bytes.is_some() {
+                    return Err(scroll::Error::Custom(format!(
+                        "layer {} of material {} can't be converted to or from version 1100",
+                        j, i
+                    )));
+                }
+
+                if version > 800 {
+                    layer.hd.get_or_insert_with(LayerHd::default);
+                } else {
+                    layer.hd = None;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl BytesTotalSize for MaterialChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for material in &self.data {
+            result += material.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Material {
+    pub priority_plane: u32,
+    pub flags: u32, // 0x1: constant color 0x10: sort primitives far z 0x20: full resolution
+    // max length 80, version 900+
+    pub shader: Option<String>,
+
+    pub layers: Vec<Layer>, // LAYS
+}
+
+inclusive_size_impl!(Material);
+
+impl ctx::TryFromCtx<'_, (Endian, u32)> for Material {
+    type Error = MdxError;
+
+    fn try_from_ctx(
+        src: &[u8],
+        (ctx, version): (Endian, u32),
+    ) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+        let priority_plane = src.gread_with::<u32>(offset, ctx)?;
+        let flags = src.gread_with::<u32>(offset, ctx)?;
+
+        let mut shader = None;
+        if version > 800 {
+            // Shader has fixed size
+            let max_shader_len = 80usize;
+            shader = Some(src.gread::<&str>(&mut offset.clone())?.to_string());
+            *offset += max_shader_len;
+        }
+
+        // LAYS
+        let tag = src.gread_with::<u32>(offset, ctx)?;
+        if tag != LAYS_TAG {
+            return Err(MdxError::new(
+                *offset - 4,
+                MdxErrorKind::UnexpectedTag {
+                    expected: FourCC(LAYS_TAG),
+                    found: FourCC(tag),
+                },
+            ));
+        }
+
+        // inclusive_size at least
+        let layers_count = read_count(src, offset, ctx, 4)?;
+        let mut layers = Vec::new();
+        for _ in 0..layers_count {
+            let layer: Layer = read_within(src, offset, (ctx, version), || {
+                format!("layer {}", layers.len())
+            })?;
+            layers.push(layer);
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((
+            Material {
+                priority_plane,
+                flags,
+                shader,
+                layers,
+            },
+            *offset,
+        ))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for Material {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+        src.gwrite_with::<u32>(self.priority_plane, offset, ctx)?;
+        src.gwrite_with::<u32>(self.flags, offset, ctx)?;
+
+        if let Some(shader) = self.shader {
+            // Shader has fixed size
+            let max_shader_len = 80usize;
+            let null_offset = &mut offset.clone();
+            for _ in 0..max_shader_len {
+                src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+            }
+            src.gwrite_with::<&str>(shader.as_ref(), &mut offset.clone(), ())?;
+            *offset += max_shader_len;
+        }
+
+        // LAYS
+        src.gwrite_with::<u32>(LAYS_TAG, offset, ctx)?;
+
+        src.gwrite_with::<u32>(self.layers.len() as u32, offset, ctx)?;
+        for layer in self.layers {
+            src.gwrite_with::<Layer>(layer, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for Material {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+        result += size_of_val(&self.priority_plane);
+        result += size_of_val(&self.flags);
+
+        if self.shader.is_some() {
+            let max_shader_len = 80usize;
+            result += max_shader_len;
+        }
+
+        result += 4; // LAYS
+        result += 4; // count
+        for layer in &self.layers {
+            result += layer.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Layer {
+    pub filter_mode: u32, // 0: none 1: transparent 2: blend 3: additive 4: add alpha 5: modulate 6: modulate 2x
+    pub shading_flags: u32,
+    pub texture_id: u32,
+    pub texture_animation_id: u32,
+    pub coord_id: u32,
+    pub alpha: f32,
+    pub hd: Option<LayerHd>, // version 900+
+    // Version 1100+ adds a list of textures after the HD fields. Its layout isn't
+    // known, so everything after the HD fields, animated blocks included, is kept
+    // raw and written back unchanged.
+    pub unknown_bytes: Option<Vec<u8>>,
+
+    pub texture_id_transform: Option<Transform<u32>>,
+    pub alpha_transform: Option<Transform<f32>>,
+    pub emissive_gain_transform: Option<Transform<f32>>,
+    pub fresnel_color_transform: Option<Transform<Vec3>>,
+    pub fresnel_alpha_transform: Option<Transform<f32>>,
+    pub fresnel_team_color_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
+}
+
+inclusive_size_impl!(Layer);
+
+impl ctx::TryFromCtx<'_, (Endian, u32)> for Layer {
+    type Error = MdxError;
+
+    fn try_from_ctx(
+        src: &[u8],
+        (ctx, version): (Endian, u32),
+    ) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+        let filter_mode = src.gread_with::<u32>(offset, ctx)?;
+        let shading_flags = src.gread_with::<u32>(offset, ctx)?;
+        let texture_id = src.gread_with::<u32>(offset, ctx)?;
+        let texture_animation_id = src.gread_with::<u32>(offset, ctx)?;
+        let coord_id = src.gread_with::<u32>(offset, ctx)?;
+        let alpha = src.gread_with::<f32>(offset, ctx)?;
+
+        let mut hd = None;
+        if version > 800 {
+            let value: LayerHd = read_within(src, offset, ctx, || "HD".to_string())?;
+            hd = Some(value);
+        }
+
+        let mut unknown_bytes = None;
+        if version > 1000 {
+            let end = (inclusive_size as usize).max(*offset);
+            let bytes = src
+                .get(*offset..end)
+                .ok_or_else(|| MdxError::new(*offset, MdxErrorKind::UnexpectedEof))?;
+            unknown_bytes = Some(bytes.to_vec());
+            *offset = end;
+        }
+
+        let mut layer = Layer {
+            filter_mode,
+            shading_flags,
+            texture_id,
+            texture_animation_id,
+            coord_id,
+            alpha,
+            hd,
+            unknown_bytes,
+            texture_id_transform: None,
+            alpha_transform: None,
+            emissive_gain_transform: None,
+            fresnel_color_transform: None,
+            fresnel_alpha_transform: None,
+            fresnel_team_color_transform: None,
+            unknown_tracks: None,
+        };
+
+        while (*offset as u32) < inclusive_size {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+
+            match tag {
+                KMTF_TAG => {
+                    let kmtf = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.texture_id_transform = Some(kmtf);
+                }
+                KMTA_TAG => {
+                    let kmta = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.alpha_transform = Some(kmta);
+                }
+                KMTE_TAG => {
+                    let kmte = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.emissive_gain_transform = Some(kmte);
+                }
+                KFC3_TAG => {
+                    let kfc3 = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.fresnel_color_transform = Some(kfc3);
+                }
+                KFCA_TAG => {
+                    let kfca = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.fresnel_alpha_transform = Some(kfca);
+                }
+                KFTC_TAG => {
+                    let kftc = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    layer.fresnel_team_color_transform = Some(kftc);
+                }
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    layer.unknown_tracks = Some(unknown_tracks);
+                }
+            }
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((layer, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for Layer {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+        src.gwrite_with::<u32>(self.filter_mode, offset, ctx)?;
+        src.gwrite_with::<u32>(self.shading_flags, offset, ctx)?;
+        src.gwrite_with::<u32>(self.texture_id, offset, ctx)?;
+        src.gwrite_with::<u32>(self.texture_animation_id, offset, ctx)?;
+        src.gwrite_with::<u32>(self.coord_id, offset, ctx)?;
+        src.gwrite_with::<f32>(self.alpha, offset, ctx)?;
+
+        if let Some(hd) = self.hd {
+            src.gwrite_with::<LayerHd>(hd, offset, ctx)?;
+        }
+        if let Some(unknown_bytes) = self.unknown_bytes {
+            src.gwrite_with::<&[u8]>(&unknown_bytes, offset, ())?;
+        }
+
+        if let Some(texture_id_transform) = self.texture_id_transform {
+            src.gwrite_with::<u32>(KMTF_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<u32>>(texture_id_transform, offset, ctx)?;
+        }
+        if let Some(alpha_transform) = self.alpha_transform {
+            src.gwrite_with::<u32>(KMTA_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(alpha_transform, offset, ctx)?;
+        }
+        if let Some(emissive_gain_transform) = self.emissive_gain_transform {
+            src.gwrite_with::<u32>(KMTE_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(emissive_gain_transform, offset, ctx)?;
+        }
+        if let Some(fresnel_color_transform) = self.fresnel_color_transform {
+            src.gwrite_with::<u32>(KFC3_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<Vec3>>(fresnel_color_transform, offset, ctx)?;
+        }
+        if let Some(fresnel_alpha_transform) = self.fresnel_alpha_transform {
+            src.gwrite_with::<u32>(KFCA_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(fresnel_alpha_transform, offset, ctx)?;
+        }
+        if let Some(fresnel_team_color_transform) = self.fresnel_team_color_transform {
+            src.gwrite_with::<u32>(KFTC_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(fresnel_team_color_transform, offset, ctx)?;
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for Layer {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+        result += size_of_val(&self.filter_mode);
+        result += size_of_val(&self.shading_flags);
+        result += size_of_val(&self.texture_id);
+        result += size_of_val(&self.texture_animation_id);
+        result += size_of_val(&self.coord_id);
+        result += size_of_val(&self.alpha);
+
+        if let Some(hd) = &self.hd {
+            result += hd.total_bytes_size();
+        }
+        if let Some(unknown_bytes) = &self.unknown_bytes {
+            result += unknown_bytes.len();
+        }
+
+        if let Some(texture_id_transform) = &self.texture_id_transform {
+            result += 4;
+            result += texture_id_transform.total_bytes_size();
+        }
+        if let Some(alpha_transform) = &self.alpha_transform {
+            result += 4;
+            result += alpha_transform.total_bytes_size();
+        }
+        if let Some(emissive_gain_transform) = &self.emissive_gain_transform {
+            result += 4;
+            result += emissive_gain_transform.total_bytes_size();
+        }
+        if let Some(fresnel_color_transform) = &self.fresnel_color_transform {
+            result += 4;
+            result += fresnel_color_transform.total_bytes_size();
+        }
+        if let Some(fresnel_alpha_transform) = &self.fresnel_alpha_transform {
+            result += 4;
+            result += fresnel_alpha_transform.total_bytes_size();
+        }
+        if let Some(fresnel_team_color_transform) = &self.fresnel_team_color_transform {
+            result += 4;
+            result += fresnel_team_color_transform.total_bytes_size();
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+// Static values of HD layers, version 900+.
+#[derive(PartialEq, Debug, Clone)]
+pub struct LayerHd {
+    pub emissive_gain: f32,
+    pub fresnel_color: Vec3,
+    pub fresnel_opacity: f32,
+    pub fresnel_team_color: f32,
+}
+
+impl Default for LayerHd {
+    fn default() -> Self {
+        LayerHd {
+            emissive_gain: 1.0,
+            fresnel_color: Vec3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            fresnel_opacity: 0.0,
+            fresnel_team_color: 0.0,
+        }
+    }
+}
+
+impl ctx::TryFromCtx<'_, Endian> for LayerHd {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let emissive_gain = src.gread_with::<f32>(offset, ctx)?;
+        let fresnel_color = src.gread_with::<Vec3>(offset, ctx)?;
+        let fresnel_opacity = src.gread_with::<f32>(offset, ctx)?;
+        let fresnel_team_color = src.gread_with::<f32>(offset, ctx)?;
+
+        Ok((
+            LayerHd {
+                emissive_gain,
+                fresnel_color,
+                fresnel_opacity,
+                fresnel_team_color,
+            },
+            *offset,
+        ))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for LayerHd {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<f32>(self.emissive_gain, offset, ctx)?;
+        src.gwrite_with::<Vec3>(self.fresnel_color, offset, ctx)?;
+        src.gwrite_with::<f32>(self.fresnel_opacity, offset, ctx)?;
+        src.gwrite_with::<f32>(self.fresnel_team_color, offset, ctx)?;
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for LayerHd {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        result += size_of_val(&self.emissive_gain);
+        result += self.fresnel_color.total_bytes_size();
+        result += size_of_val(&self.fresnel_opacity);
+        result += size_of_val(&self.fresnel_team_color);
 
         result
     }
@@ -1,22 +1,21 @@
 use crate::chunks::BytesTotalSize;
 use crate::chunks::Extent;
+use crate::error::{check_size, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct ModelChunk {
-    pub chunk_size: u32,
-
     pub name: String,
     pub unknown: u32,
     pub extent: Extent,
     pub blend_time: u32,
 }
 
-calculate_chunk_size_impl!(ModelChunk);
+chunk_size_impl!(ModelChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for ModelChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -30,9 +29,10 @@ impl ctx::TryFromCtx<'_, Endian> for ModelChunk {
         let unknown = src.gread_with::<u32>(offset, ctx)?;
         let extent = src.gread_with::<Extent>(offset, ctx)?;
         let blend_time = src.gread_with::<u32>(offset, ctx)?;
+        check_size(0, chunk_size, *offset - 4)?;
+
         Ok((
             ModelChunk {
-                chunk_size,
                 name,
                 unknown,
                 extent,
@@ -49,7 +49,7 @@ impl ctx::TryIntoCtx<Endian> for ModelChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         // Name has fixed size
         let max_name_len = 336usize;
@@ -73,7 +73,8 @@ impl BytesTotalSize for ModelChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         let max_name_len = 336usize;
         result += max_name_len;
@@ -1,12 +1,11 @@
-use crate::chunks::{BytesTotalSize, Transform, Vec3, Vec4};
+use crate::chunks::{BytesTotalSize, Transform, UnknownTracks, Vec3, Vec4};
 use crate::consts::{KGRT_TAG, KGSC_TAG, KGTR_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct Node {
-    pub inclusive_size: u32,
-
     // max length 80
     pub name: String,
     pub object_id: u32,
@@ -16,10 +15,23 @@ pub struct Node {
     pub translation: Option<Transform<Vec3>>,
     pub rotation: Option<Transform<Vec4>>,
     pub scaling: Option<Transform<Vec3>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(Node);
+
+// Node flags telling which kind of object owns the node
+pub(crate) const HELPER_FLAG: u32 = 0x0;
+pub(crate) const BONE_FLAG: u32 = 0x100;
+pub(crate) const LIGHT_FLAG: u32 = 0x200;
+pub(crate) const EVENT_OBJECT_FLAG: u32 = 0x400;
+pub(crate) const ATTACHMENT_FLAG: u32 = 0x800;
+pub(crate) const PARTICLE_EMITTER_FLAG: u32 = 0x1000;
+pub(crate) const COLLISION_SHAPE_FLAG: u32 = 0x2000;
+pub(crate) const RIBBON_EMITTER_FLAG: u32 = 0x4000;
+
 impl ctx::TryFromCtx<'_, Endian> for Node {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -36,7 +48,6 @@ impl ctx::TryFromCtx<'_, Endian> for Node {
         let flags = src.gread_with::<u32>(offset, ctx)?;
 
         let mut node = Node {
-            inclusive_size,
             name,
             object_id,
             parent_id,
@@ -44,28 +55,35 @@ impl ctx::TryFromCtx<'_, Endian> for Node {
             translation: None,
             rotation: None,
             scaling: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx).unwrap();
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KGTR_TAG => {
-                    let translation = src.gread_with::<Transform<Vec3>>(offset, ctx)?;
+                    let translation = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     node.translation = Some(translation);
                 }
                 KGRT_TAG => {
-                    let rotation = src.gread_with::<Transform<Vec4>>(offset, ctx)?;
+                    let rotation = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     node.rotation = Some(rotation);
                 }
                 KGSC_TAG => {
-                    let scaling = src.gread_with::<Transform<Vec3>>(offset, ctx)?;
+                    let scaling = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     node.scaling = Some(scaling);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    node.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, *offset)?;
+
         Ok((node, *offset))
     }
 }
@@ -76,7 +94,7 @@ impl ctx::TryIntoCtx<Endian> for Node {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
 
         // Name has fixed size
         let max_name_len = 80usize;
@@ -103,6 +121,10 @@ impl ctx::TryIntoCtx<Endian> for Node {
             src.gwrite_with::<u32>(KGSC_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<Vec3>>(self.scaling.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -112,7 +134,8 @@ impl BytesTotalSize for Node {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         let max_name_len = 80usize;
         result += max_name_len;
@@ -133,6 +156,10 @@ impl BytesTotalSize for Node {
             result += 4;
             result += self.scaling.as_ref().unwrap().total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
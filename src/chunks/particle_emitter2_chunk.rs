@@ -1,31 +1,40 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Color, Node, Transform, UnknownTracks};
+use crate::consts::{
+    KP2E_TAG, KP2G_TAG, KP2L_TAG, KP2N_TAG, KP2R_TAG, KP2S_TAG, KP2V_TAG, KP2W_TAG,
+};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct ParticleEmitter2Chunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<ParticleEmitter2>,
 }
 
-calculate_chunk_size_impl!(ParticleEmitter2Chunk);
+chunk_size_impl!(ParticleEmitter2Chunk);
 
 impl ctx::TryFromCtx<'_, Endian> for ParticleEmitter2Chunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let particle_emitter2: ParticleEmitter2 = read_within(src, offset, ctx, || {
+                format!("particle emitter2 {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
+            data.push(particle_emitter2);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((ParticleEmitter2Chunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((ParticleEmitter2Chunk { data }, *offset))
     }
 }
 
@@ -35,9 +44,11 @@ impl ctx::TryIntoCtx<Endian> for ParticleEmitter2Chunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for particle_emitter2 in self.data {
+            src.gwrite_with::<ParticleEmitter2>(particle_emitter2, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -47,8 +58,377 @@ impl BytesTotalSize for ParticleEmitter2Chunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for particle_emitter2 in &self.data {
+            result += particle_emitter2.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct ParticleEmitter2 {
+    pub node: Node,
+
+    pub speed: f32,
+    pub variation: f32,
+    pub latitude: f32,
+    pub gravity: f32,
+    pub life_span: f32,
+    pub emission_rate: f32,
+    pub width: f32,
+    pub length: f32,
+    pub filter_mode: u32, // 0: blend 1: additive 2: modulate 3: modulate 2x 4: alpha key
+    pub rows: u32,
+    pub columns: u32,
+    pub head_or_tail: u32, // 0: head 1: tail 2: both
+    pub tail_length: f32,
+    pub time: f32,
+    pub segment_colors: [Color; 3],
+    pub segment_alphas: [u8; 3],
+    pub segment_scaling: [f32; 3],
+    pub head_intervals: [u32; 3],       // start, end, repeat
+    pub head_decay_intervals: [u32; 3], // start, end, repeat
+    pub tail_intervals: [u32; 3],       // start, end, repeat
+    pub tail_decay_intervals: [u32; 3], // start, end, repeat
+    pub texture_id: u32,
+    pub squirt: u32,
+    pub priority_plane: u32,
+    pub replaceable_id: u32,
+
+    pub speed_transform: Option<Transform<f32>>,
+    pub variation_transform: Option<Transform<f32>>,
+    pub latitude_transform: Option<Transform<f32>>,
+    pub gravity_transform: Option<Transform<f32>>,
+    pub emission_rate_transform: Option<Transform<f32>>,
+    pub width_transform: Option<Transform<f32>>,
+    pub length_transform: Option<Transform<f32>>,
+    pub visibility_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
+}
+
+inclusive_size_impl!(ParticleEmitter2);
+
+impl ctx::TryFromCtx<'_, Endian> for ParticleEmitter2 {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let speed = src.gread_with::<f32>(offset, ctx)?;
+        let variation = src.gread_with::<f32>(offset, ctx)?;
+        let latitude = src.gread_with::<f32>(offset, ctx)?;
+        let gravity = src.gread_with::<f32>(offset, ctx)?;
+        let life_span = src.gread_with::<f32>(offset, ctx)?;
+        let emission_rate = src.gread_with::<f32>(offset, ctx)?;
+        let width = src.gread_with::<f32>(offset, ctx)?;
+        let length = src.gread_with::<f32>(offset, ctx)?;
+        let filter_mode = src.gread_with::<u32>(offset, ctx)?;
+        let rows = src.gread_with::<u32>(offset, ctx)?;
+        let columns = src.gread_with::<u32>(offset, ctx)?;
+        let head_or_tail = src.gread_with::<u32>(offset, ctx)?;
+        let tail_length = src.gread_with::<f32>(offset, ctx)?;
+        let time = src.gread_with::<f32>(offset, ctx)?;
+
+        let segment_colors = [
+            src.gread_with::<Color>(offset, ctx)?,
+            src.gread_with::<Color>(offset, ctx)?,
+            src.gread_with::<Color>(offset, ctx)?,
+        ];
+
+        let mut segment_alphas = [0u8; 3];
+        for alpha in segment_alphas.iter_mut() {
+            *alpha = src.gread_with::<u8>(offset, ctx)?;
+        }
+        let mut segment_scaling = [0f32; 3];
+        for scaling in segment_scaling.iter_mut() {
+            *scaling = src.gread_with::<f32>(offset, ctx)?;
+        }
+
+        let mut head_intervals = [0u32; 3];
+        for value in head_intervals.iter_mut() {
+            *value = src.gread_with::<u32>(offset, ctx)?;
+        }
+        let mut head_decay_intervals = [0u32; 3];
+        for value in head_decay_intervals.iter_mut() {
+            *value = src.gread_with::<u32>(offset, ctx)?;
+        }
+        let mut tail_intervals = [0u32; 3];
+        for value in tail_intervals.iter_mut() {
+            *value = src.gread_with::<u32>(offset, ctx)?;
+        }
+        let mut tail_decay_intervals = [0u32; 3];
+        for value in tail_decay_intervals.iter_mut() {
+            *value = src.gread_with::<u32>(offset, ctx)?;
+        }
+
+        let texture_id = src.gread_with::<u32>(offset, ctx)?;
+        let squirt = src.gread_with::<u32>(offset, ctx)?;
+        let priority_plane = src.gread_with::<u32>(offset, ctx)?;
+        let replaceable_id = src.gread_with::<u32>(offset, ctx)?;
+
+        let mut particle_emitter2 = ParticleEmitter2 {
+            node,
+            speed,
+            variation,
+            latitude,
+            gravity,
+            life_span,
+            emission_rate,
+            width,
+            length,
+            filter_mode,
+            rows,
+            columns,
+            head_or_tail,
+            tail_length,
+            time,
+            segment_colors,
+            segment_alphas,
+            segment_scaling,
+            head_intervals,
+            head_decay_intervals,
+            tail_intervals,
+            tail_decay_intervals,
+            texture_id,
+            squirt,
+            priority_plane,
+            replaceable_id,
+            speed_transform: None,
+            variation_transform: None,
+            latitude_transform: None,
+            gravity_transform: None,
+            emission_rate_transform: None,
+            width_transform: None,
+            length_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        while (*offset as u32) < inclusive_size {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+
+            match tag {
+                KP2S_TAG => {
+                    let kp2s = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.speed_transform = Some(kp2s);
+                }
+                KP2R_TAG => {
+                    let kp2r = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.variation_transform = Some(kp2r);
+                }
+                KP2L_TAG => {
+                    let kp2l = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.latitude_transform = Some(kp2l);
+                }
+                KP2G_TAG => {
+                    let kp2g = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.gravity_transform = Some(kp2g);
+                }
+                KP2E_TAG => {
+                    let kp2e = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.emission_rate_transform = Some(kp2e);
+                }
+                KP2W_TAG => {
+                    let kp2w = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.width_transform = Some(kp2w);
+                }
+                KP2N_TAG => {
+                    let kp2n = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.length_transform = Some(kp2n);
+                }
+                KP2V_TAG => {
+                    let kp2v = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter2.visibility_transform = Some(kp2v);
+                }
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    particle_emitter2.unknown_tracks = Some(unknown_tracks);
+                }
+            }
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((particle_emitter2, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for ParticleEmitter2 {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        src.gwrite_with::<f32>(self.speed, offset, ctx)?;
+        src.gwrite_with::<f32>(self.variation, offset, ctx)?;
+        src.gwrite_with::<f32>(self.latitude, offset, ctx)?;
+        src.gwrite_with::<f32>(self.gravity, offset, ctx)?;
+        src.gwrite_with::<f32>(self.life_span, offset, ctx)?;
+        src.gwrite_with::<f32>(self.emission_rate, offset, ctx)?;
+        src.gwrite_with::<f32>(self.width, offset, ctx)?;
+        src.gwrite_with::<f32>(self.length, offset, ctx)?;
+        src.gwrite_with::<u32>(self.filter_mode, offset, ctx)?;
+        src.gwrite_with::<u32>(self.rows, offset, ctx)?;
+        src.gwrite_with::<u32>(self.columns, offset, ctx)?;
+        src.gwrite_with::<u32>(self.head_or_tail, offset, ctx)?;
+        src.gwrite_with::<f32>(self.tail_length, offset, ctx)?;
+        src.gwrite_with::<f32>(self.time, offset, ctx)?;
+
+        for color in self.segment_colors.iter() {
+            src.gwrite_with::<Color>(color.clone(), offset, ctx)?;
+        }
+        for alpha in self.segment_alphas.iter() {
+            src.gwrite_with::<u8>(*alpha, offset, ctx)?;
+        }
+        for scaling in self.segment_scaling.iter() {
+            src.gwrite_with::<f32>(*scaling, offset, ctx)?;
+        }
+
+        for value in self.head_intervals.iter() {
+            src.gwrite_with::<u32>(*value, offset, ctx)?;
+        }
+        for value in self.head_decay_intervals.iter() {
+            src.gwrite_with::<u32>(*value, offset, ctx)?;
+        }
+        for value in self.tail_intervals.iter() {
+            src.gwrite_with::<u32>(*value, offset, ctx)?;
+        }
+        for value in self.tail_decay_intervals.iter() {
+            src.gwrite_with::<u32>(*value, offset, ctx)?;
+        }
+
+        src.gwrite_with::<u32>(self.texture_id, offset, ctx)?;
+        src.gwrite_with::<u32>(self.squirt, offset, ctx)?;
+        src.gwrite_with::<u32>(self.priority_plane, offset, ctx)?;
+        src.gwrite_with::<u32>(self.replaceable_id, offset, ctx)?;
+
+        if let Some(speed_transform) = self.speed_transform {
+            src.gwrite_with::<u32>(KP2S_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(speed_transform, offset, ctx)?;
+        }
+        if let Some(variation_transform) = self.variation_transform {
+            src.gwrite_with::<u32>(KP2R_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(variation_transform, offset, ctx)?;
+        }
+        if let Some(latitude_transform) = self.latitude_transform {
+            src.gwrite_with::<u32>(KP2L_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(latitude_transform, offset, ctx)?;
+        }
+        if let Some(gravity_transform) = self.gravity_transform {
+            src.gwrite_with::<u32>(KP2G_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(gravity_transform, offset, ctx)?;
+        }
+        if let Some(emission_rate_transform) = self.emission_rate_transform {
+            src.gwrite_with::<u32>(KP2E_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(emission_rate_transform, offset, ctx)?;
+        }
+        if let Some(width_transform) = self.width_transform {
+            src.gwrite_with::<u32>(KP2W_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(width_transform, offset, ctx)?;
+        }
+        if let Some(length_transform) = self.length_transform {
+            src.gwrite_with::<u32>(KP2N_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(length_transform, offset, ctx)?;
+        }
+        if let Some(visibility_transform) = self.visibility_transform {
+            src.gwrite_with::<u32>(KP2V_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(visibility_transform, offset, ctx)?;
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for ParticleEmitter2 {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+
+        result += self.node.total_bytes_size();
+
+        result += size_of_val(&self.speed);
+        result += size_of_val(&self.variation);
+        result += size_of_val(&self.latitude);
+        result += size_of_val(&self.gravity);
+        result += size_of_val(&self.life_span);
+        result += size_of_val(&self.emission_rate);
+        result += size_of_val(&self.width);
+        result += size_of_val(&self.length);
+        result += size_of_val(&self.filter_mode);
+        result += size_of_val(&self.rows);
+        result += size_of_val(&self.columns);
+        result += size_of_val(&self.head_or_tail);
+        result += size_of_val(&self.tail_length);
+        result += size_of_val(&self.time);
+        for color in self.segment_colors.iter() {
+            result += color.total_bytes_size();
+        }
+        result += size_of_val(&self.segment_alphas);
+        result += size_of_val(&self.segment_scaling);
+        result += size_of_val(&self.head_intervals);
+        result += size_of_val(&self.head_decay_intervals);
+        result += size_of_val(&self.tail_intervals);
+        result += size_of_val(&self.tail_decay_intervals);
+        result += size_of_val(&self.texture_id);
+        result += size_of_val(&self.squirt);
+        result += size_of_val(&self.priority_plane);
+        result += size_of_val(&self.replaceable_id);
+
+        if let Some(speed_transform) = &self.speed_transform {
+            result += 4;
+            result += speed_transform.total_bytes_size();
+        }
+        if let Some(variation_transform) = &self.variation_transform {
+            result += 4;
+            result += variation_transform.total_bytes_size();
+        }
+        if let Some(latitude_transform) = &self.latitude_transform {
+            result += 4;
+            result += latitude_transform.total_bytes_size();
+        }
+        if let Some(gravity_transform) = &self.gravity_transform {
+            result += 4;
+            result += gravity_transform.total_bytes_size();
+        }
+        if let Some(emission_rate_transform) = &self.emission_rate_transform {
+            result += 4;
+            result += emission_rate_transform.total_bytes_size();
+        }
+        if let Some(width_transform) = &self.width_transform {
+            result += 4;
+            result += width_transform.total_bytes_size();
+        }
+        if let Some(length_transform) = &self.length_transform {
+            result += 4;
+            result += length_transform.total_bytes_size();
+        }
+        if let Some(visibility_transform) = &self.visibility_transform {
+            result += 4;
+            result += visibility_transform.total_bytes_size();
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,31 +1,38 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Node, Transform, UnknownTracks};
+use crate::consts::{KPEE_TAG, KPEG_TAG, KPEL_TAG, KPES_TAG, KPEV_TAG, KPLN_TAG, KPLT_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct ParticleEmitterChunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<ParticleEmitter>,
 }
 
-calculate_chunk_size_impl!(ParticleEmitterChunk);
+chunk_size_impl!(ParticleEmitterChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for ParticleEmitterChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let particle_emitter: ParticleEmitter = read_within(src, offset, ctx, || {
+                format!("particle emitter {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
+            data.push(particle_emitter);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((ParticleEmitterChunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((ParticleEmitterChunk { data }, *offset))
     }
 }
 
@@ -35,9 +42,11 @@ impl ctx::TryIntoCtx<Endian> for ParticleEmitterChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for particle_emitter in self.data {
+            src.gwrite_with::<ParticleEmitter>(particle_emitter, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -47,8 +56,245 @@ impl BytesTotalSize for ParticleEmitterChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for particle_emitter in &self.data {
+            result += particle_emitter.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct ParticleEmitter {
+    pub node: Node,
+
+    pub emission_rate: f32,
+    pub gravity: f32,
+    pub longitude: f32,
+    pub latitude: f32,
+    // max length 260
+    pub path: String,
+    pub life_span: f32,
+    pub speed: f32,
+
+    pub emission_rate_transform: Option<Transform<f32>>,
+    pub gravity_transform: Option<Transform<f32>>,
+    pub longitude_transform: Option<Transform<f32>>,
+    pub latitude_transform: Option<Transform<f32>>,
+    pub life_span_transform: Option<Transform<f32>>,
+    pub speed_transform: Option<Transform<f32>>,
+    pub visibility_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
+}
+
+inclusive_size_impl!(ParticleEmitter);
+
+impl ctx::TryFromCtx<'_, Endian> for ParticleEmitter {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let emission_rate = src.gread_with::<f32>(offset, ctx)?;
+        let gravity = src.gread_with::<f32>(offset, ctx)?;
+        let longitude = src.gread_with::<f32>(offset, ctx)?;
+        let latitude = src.gread_with::<f32>(offset, ctx)?;
+
+        let max_path_len = 260usize;
+        let path = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_path_len;
+
+        let life_span = src.gread_with::<f32>(offset, ctx)?;
+        let speed = src.gread_with::<f32>(offset, ctx)?;
+
+        let mut particle_emitter = ParticleEmitter {
+            node,
+            emission_rate,
+            gravity,
+            longitude,
+            latitude,
+            path,
+            life_span,
+            speed,
+            emission_rate_transform: None,
+            gravity_transform: None,
+            longitude_transform: None,
+            latitude_transform: None,
+            life_span_transform: None,
+            speed_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        while (*offset as u32) < inclusive_size {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+
+            match tag {
+                KPEE_TAG => {
+                    let kpee = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.emission_rate_transform = Some(kpee);
+                }
+                KPEG_TAG => {
+                    let kpeg = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.gravity_transform = Some(kpeg);
+                }
+                KPLN_TAG => {
+                    let kpln = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.longitude_transform = Some(kpln);
+                }
+                KPLT_TAG => {
+                    let kplt = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.latitude_transform = Some(kplt);
+                }
+                KPEL_TAG => {
+                    let kpel = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.life_span_transform = Some(kpel);
+                }
+                KPES_TAG => {
+                    let kpes = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.speed_transform = Some(kpes);
+                }
+                KPEV_TAG => {
+                    let kpev = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    particle_emitter.visibility_transform = Some(kpev);
+                }
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    particle_emitter.unknown_tracks = Some(unknown_tracks);
+                }
+            }
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((particle_emitter, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for ParticleEmitter {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        src.gwrite_with::<f32>(self.emission_rate, offset, ctx)?;
+        src.gwrite_with::<f32>(self.gravity, offset, ctx)?;
+        src.gwrite_with::<f32>(self.longitude, offset, ctx)?;
+        src.gwrite_with::<f32>(self.latitude, offset, ctx)?;
+
+        // String has fixed size
+        let max_path_len = 260usize;
+        let null_offset = &mut offset.clone();
+        for _ in 0..max_path_len {
+            src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+        }
+        src.gwrite_with::<&str>(self.path.as_ref(), &mut offset.clone(), ())?;
+        *offset += max_path_len;
+
+        src.gwrite_with::<f32>(self.life_span, offset, ctx)?;
+        src.gwrite_with::<f32>(self.speed, offset, ctx)?;
+
+        if let Some(emission_rate_transform) = self.emission_rate_transform {
+            src.gwrite_with::<u32>(KPEE_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(emission_rate_transform, offset, ctx)?;
+        }
+        if let Some(gravity_transform) = self.gravity_transform {
+            src.gwrite_with::<u32>(KPEG_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(gravity_transform, offset, ctx)?;
+        }
+        if let Some(longitude_transform) = self.longitude_transform {
+            src.gwrite_with::<u32>(KPLN_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(longitude_transform, offset, ctx)?;
+        }
+        if let Some(latitude_transform) = self.latitude_transform {
+            src.gwrite_with::<u32>(KPLT_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(latitude_transform, offset, ctx)?;
+        }
+        if let Some(life_span_transform) = self.life_span_transform {
+            src.gwrite_with::<u32>(KPEL_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(life_span_transform, offset, ctx)?;
+        }
+        if let Some(speed_transform) = self.speed_transform {
+            src.gwrite_with::<u32>(KPES_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(speed_transform, offset, ctx)?;
+        }
+        if let Some(visibility_transform) = self.visibility_transform {
+            src.gwrite_with::<u32>(KPEV_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(visibility_transform, offset, ctx)?;
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for ParticleEmitter {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+
+        result += self.node.total_bytes_size();
+
+        result += size_of_val(&self.emission_rate);
+        result += size_of_val(&self.gravity);
+        result += size_of_val(&self.longitude);
+        result += size_of_val(&self.latitude);
+
+        let max_path_len = 260usize;
+        result += max_path_len;
+
+        result += size_of_val(&self.life_span);
+        result += size_of_val(&self.speed);
+
+        if let Some(emission_rate_transform) = &self.emission_rate_transform {
+            result += 4;
+            result += emission_rate_transform.total_bytes_size();
+        }
+        if let Some(gravity_transform) = &self.gravity_transform {
+            result += 4;
+            result += gravity_transform.total_bytes_size();
+        }
+        if let Some(longitude_transform) = &self.longitude_transform {
+            result += 4;
+            result += longitude_transform.total_bytes_size();
+        }
+        if let Some(latitude_transform) = &self.latitude_transform {
+            result += 4;
+            result += latitude_transform.total_bytes_size();
+        }
+        if let Some(life_span_transform) = &self.life_span_transform {
+            result += 4;
+            result += life_span_transform.total_bytes_size();
+        }
+        if let Some(speed_transform) = &self.speed_transform {
+            result += 4;
+            result += speed_transform.total_bytes_size();
+        }
+        if let Some(visibility_transform) = &self.visibility_transform {
+            result += 4;
+            result += visibility_transform.total_bytes_size();
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,19 +1,18 @@
 use crate::chunks::BytesTotalSize;
+use crate::error::{check_size, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct PivotPointChunk {
-    pub chunk_size: u32,
-
     // chunk_size / 12
     pub data: Vec<PivotPoint>,
 }
 
-calculate_chunk_size_impl!(PivotPointChunk);
+chunk_size_impl!(PivotPointChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for PivotPointChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -22,12 +21,15 @@ impl ctx::TryFromCtx<'_, Endian> for PivotPointChunk {
         let mut data = Vec::new();
         if let Some(pivot_point_count) = u32::checked_div(chunk_size.clone(), 12) {
             for _ in 0..pivot_point_count {
-                let pivot_point = src.gread_with::<PivotPoint>(offset, ctx)?;
+                let pivot_point: PivotPoint =
+                    read_within(src, offset, ctx, || format!("pivot point {}", data.len()))?;
                 data.push(pivot_point);
             }
         }
 
-        Ok((PivotPointChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((PivotPointChunk { data }, *offset))
     }
 }
 
@@ -37,7 +39,7 @@ impl ctx::TryIntoCtx<Endian> for PivotPointChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for pivot_point in self.data {
             src.gwrite_with::<PivotPoint>(pivot_point, offset, ctx)?;
@@ -51,7 +53,8 @@ impl BytesTotalSize for PivotPointChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for pivot_point in &self.data {
             result += pivot_point.total_bytes_size();
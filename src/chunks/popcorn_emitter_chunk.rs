@@ -0,0 +1,292 @@
+use crate::chunks::{BytesTotalSize, Color, Node, Transform, UnknownTracks};
+use crate::consts::{KPPA_TAG, KPPC_TAG, KPPE_TAG, KPPL_TAG, KPPS_TAG, KPPV_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
+use scroll::{ctx, Endian, Pread, Pwrite};
+use std::mem::size_of_val;
+
+#[derive(PartialEq, Debug)]
+pub struct PopcornEmitterChunk {
+    pub data: Vec<PopcornEmitter>,
+}
+
+chunk_size_impl!(PopcornEmitterChunk);
+
+impl ctx::TryFromCtx<'_, Endian> for PopcornEmitterChunk {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let chunk_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let popcorn_emitter: PopcornEmitter = read_within(src, offset, ctx, || {
+                format!("popcorn emitter {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
+            data.push(popcorn_emitter);
+        }
+
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((PopcornEmitterChunk { data }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for PopcornEmitterChunk {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
+
+        for popcorn_emitter in self.data {
+            src.gwrite_with::<PopcornEmitter>(popcorn_emitter, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for PopcornEmitterChunk {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // chunk_size
+        result += 4;
+
+        for popcorn_emitter in &self.data {
+            result += popcorn_emitter.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct PopcornEmitter {
+    pub node: Node,
+
+    pub life_span: f32,
+    pub emission_rate: f32,
+    pub speed: f32,
+    pub color: Color,
+    pub alpha: f32,
+    pub replaceable_id: u32,
+    // max length 260
+    pub path: String,
+    // max length 260
+    pub animation_visibility_guide: String,
+
+    pub alpha_transform: Option<Transform<f32>>,
+    pub color_transform: Option<Transform<Color>>,
+    pub emission_rate_transform: Option<Transform<f32>>,
+    pub life_span_transform: Option<Transform<f32>>,
+    pub speed_transform: Option<Transform<f32>>,
+    pub visibility_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
+}
+
+inclusive_size_impl!(PopcornEmitter);
+
+impl ctx::TryFromCtx<'_, Endian> for PopcornEmitter {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let life_span = src.gread_with::<f32>(offset, ctx)?;
+        let emission_rate = src.gread_with::<f32>(offset, ctx)?;
+        let speed = src.gread_with::<f32>(offset, ctx)?;
+        let color = src.gread_with::<Color>(offset, ctx)?;
+        let alpha = src.gread_with::<f32>(offset, ctx)?;
+        let replaceable_id = src.gread_with::<u32>(offset, ctx)?;
+
+        let max_path_len = 260usize;
+        let path = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_path_len;
+        let animation_visibility_guide = src.gread::<&str>(&mut offset.clone())?.to_string();
+        *offset += max_path_len;
+
+        let mut popcorn_emitter = PopcornEmitter {
+            node,
+            life_span,
+            emission_rate,
+            speed,
+            color,
+            alpha,
+            replaceable_id,
+            path,
+            animation_visibility_guide,
+            alpha_transform: None,
+            color_transform: None,
+            emission_rate_transform: None,
+            life_span_transform: None,
+            speed_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        while (*offset as u32) < inclusive_size {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+
+            match tag {
+                KPPA_TAG => {
+                    let kppa = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.alpha_transform = Some(kppa);
+                }
+                KPPC_TAG => {
+                    let kppc = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.color_transform = Some(kppc);
+                }
+                KPPE_TAG => {
+                    let kppe = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.emission_rate_transform = Some(kppe);
+                }
+                KPPL_TAG => {
+                    let kppl = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.life_span_transform = Some(kppl);
+                }
+                KPPS_TAG => {
+                    let kpps = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.speed_transform = Some(kpps);
+                }
+                KPPV_TAG => {
+                    let kppv = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    popcorn_emitter.visibility_transform = Some(kppv);
+                }
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    popcorn_emitter.unknown_tracks = Some(unknown_tracks);
+                }
+            }
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((popcorn_emitter, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for PopcornEmitter {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        src.gwrite_with::<f32>(self.life_span, offset, ctx)?;
+        src.gwrite_with::<f32>(self.emission_rate, offset, ctx)?;
+        src.gwrite_with::<f32>(self.speed, offset, ctx)?;
+        src.gwrite_with::<Color>(self.color, offset, ctx)?;
+        src.gwrite_with::<f32>(self.alpha, offset, ctx)?;
+        src.gwrite_with::<u32>(self.replaceable_id, offset, ctx)?;
+
+        // Strings have fixed size
+        let max_path_len = 260usize;
+        for path in &[self.path, self.animation_visibility_guide] {
+            let null_offset = &mut offset.clone();
+            for _ in 0..max_path_len {
+                src.gwrite_with::<u8>(0x0, null_offset, ctx)?;
+            }
+            src.gwrite_with::<&str>(path.as_ref(), &mut offset.clone(), ())?;
+            *offset += max_path_len;
+        }
+
+        if let Some(alpha_transform) = self.alpha_transform {
+            src.gwrite_with::<u32>(KPPA_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(alpha_transform, offset, ctx)?;
+        }
+        if let Some(color_transform) = self.color_transform {
+            src.gwrite_with::<u32>(KPPC_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<Color>>(color_transform, offset, ctx)?;
+        }
+        if let Some(emission_rate_transform) = self.emission_rate_transform {
+            src.gwrite_with::<u32>(KPPE_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(emission_rate_transform, offset, ctx)?;
+        }
+        if let Some(life_span_transform) = self.life_span_transform {
+            src.gwrite_with::<u32>(KPPL_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(life_span_transform, offset, ctx)?;
+        }
+        if let Some(speed_transform) = self.speed_transform {
+            src.gwrite_with::<u32>(KPPS_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(speed_transform, offset, ctx)?;
+        }
+        if let Some(visibility_transform) = self.visibility_transform {
+            src.gwrite_with::<u32>(KPPV_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(visibility_transform, offset, ctx)?;
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for PopcornEmitter {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+
+        result += self.node.total_bytes_size();
+
+        result += size_of_val(&self.life_span);
+        result += size_of_val(&self.emission_rate);
+        result += size_of_val(&self.speed);
+        result += self.color.total_bytes_size();
+        result += size_of_val(&self.alpha);
+        result += size_of_val(&self.replaceable_id);
+
+        let max_path_len = 260usize;
+        result += max_path_len; // path
+        result += max_path_len; // animation visibility guide
+
+        if let Some(alpha_transform) = &self.alpha_transform {
+            result += 4;
+            result += alpha_transform.total_bytes_size();
+        }
+        if let Some(color_transform) = &self.color_transform {
+            result += 4;
+            result += color_transform.total_bytes_size();
+        }
+        if let Some(emission_rate_transform) = &self.emission_rate_transform {
+            result += 4;
+            result += emission_rate_transform.total_bytes_size();
+        }
+        if let Some(life_span_transform) = &self.life_span_transform {
+            result += 4;
+            result += life_span_transform.total_bytes_size();
+        }
+        if let Some(speed_transform) = &self.speed_transform {
+            result += 4;
+            result += speed_transform.total_bytes_size();
+        }
+        if let Some(visibility_transform) = &self.visibility_transform {
+            result += 4;
+            result += visibility_transform.total_bytes_size();
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
+
+        result
+    }
+}
@@ -1,31 +1,38 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Color, Node, Transform, UnknownTracks};
+use crate::consts::{KRAL_TAG, KRCO_TAG, KRHA_TAG, KRHB_TAG, KRTX_TAG, KRVS_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct RibbonEmitterChunk {
-    pub chunk_size: u32,
-
-    pub bytes: Vec<u8>,
+    pub data: Vec<RibbonEmitter>,
 }
 
-calculate_chunk_size_impl!(RibbonEmitterChunk);
+chunk_size_impl!(RibbonEmitterChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for RibbonEmitterChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
         let chunk_size = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut bytes = Vec::with_capacity(chunk_size as usize);
-        unsafe {
-            bytes.set_len(chunk_size as usize);
+        let mut data = Vec::new();
+        let mut total_size = 0u32;
+        while total_size < chunk_size {
+            let start = *offset;
+            let ribbon_emitter: RibbonEmitter = read_within(src, offset, ctx, || {
+                format!("ribbon emitter {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
+            data.push(ribbon_emitter);
         }
-        src.gread_inout_with(offset, &mut bytes, ctx)?;
 
-        Ok((RibbonEmitterChunk { chunk_size, bytes }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((RibbonEmitterChunk { data }, *offset))
     }
 }
 
@@ -35,9 +42,11 @@ impl ctx::TryIntoCtx<Endian> for RibbonEmitterChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
-        src.gwrite_with::<&[u8]>(self.bytes.as_slice(), offset, ())?;
+        for ribbon_emitter in self.data {
+            src.gwrite_with::<RibbonEmitter>(ribbon_emitter, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -47,8 +56,234 @@ impl BytesTotalSize for RibbonEmitterChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
-        result += &self.bytes.capacity();
+        // chunk_size
+        result += 4;
+
+        for ribbon_emitter in &self.data {
+            result += ribbon_emitter.total_bytes_size();
+        }
+
+        result
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct RibbonEmitter {
+    pub node: Node,
+
+    pub height_above: f32,
+    pub height_below: f32,
+    pub alpha: f32,
+    pub color: Color,
+    pub life_span: f32,
+    pub texture_slot: u32,
+    pub emission_rate: u32,
+    pub rows: u32,
+    pub columns: u32,
+    pub material_id: u32,
+    pub gravity: f32,
+
+    pub height_above_transform: Option<Transform<f32>>,
+    pub height_below_transform: Option<Transform<f32>>,
+    pub alpha_transform: Option<Transform<f32>>,
+    pub color_transform: Option<Transform<Color>>,
+    pub texture_slot_transform: Option<Transform<u32>>,
+    pub visibility_transform: Option<Transform<f32>>,
+    pub unknown_tracks: Option<UnknownTracks>,
+}
+
+inclusive_size_impl!(RibbonEmitter);
+
+impl ctx::TryFromCtx<'_, Endian> for RibbonEmitter {
+    type Error = MdxError;
+
+    fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
+
+        let node: Node = read_at(src, offset, ctx)?;
+
+        let height_above = src.gread_with::<f32>(offset, ctx)?;
+        let height_below = src.gread_with::<f32>(offset, ctx)?;
+        let alpha = src.gread_with::<f32>(offset, ctx)?;
+        let color = src.gread_with::<Color>(offset, ctx)?;
+        let life_span = src.gread_with::<f32>(offset, ctx)?;
+        let texture_slot = src.gread_with::<u32>(offset, ctx)?;
+        let emission_rate = src.gread_with::<u32>(offset, ctx)?;
+        let rows = src.gread_with::<u32>(offset, ctx)?;
+        let columns = src.gread_with::<u32>(offset, ctx)?;
+        let material_id = src.gread_with::<u32>(offset, ctx)?;
+        let gravity = src.gread_with::<f32>(offset, ctx)?;
+
+        let mut ribbon_emitter = RibbonEmitter {
+            node,
+            height_above,
+            height_below,
+            alpha,
+            color,
+            life_span,
+            texture_slot,
+            emission_rate,
+            rows,
+            columns,
+            material_id,
+            gravity,
+            height_above_transform: None,
+            height_below_transform: None,
+            alpha_transform: None,
+            color_transform: None,
+            texture_slot_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        while (*offset as u32) < inclusive_size {
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
+
+            match tag {
+                KRHA_TAG => {
+                    let krha = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.height_above_transform = Some(krha);
+                }
+                KRHB_TAG => {
+                    let krhb = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.height_below_transform = Some(krhb);
+                }
+                KRAL_TAG => {
+                    let kral = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.alpha_transform = Some(kral);
+                }
+                KRCO_TAG => {
+                    let krco = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.color_transform = Some(krco);
+                }
+                KRTX_TAG => {
+                    let krtx = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.texture_slot_transform = Some(krtx);
+                }
+                KRVS_TAG => {
+                    let krvs = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
+                    ribbon_emitter.visibility_transform = Some(krvs);
+                }
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    ribbon_emitter.unknown_tracks = Some(unknown_tracks);
+                }
+            }
+        }
+
+        check_size(0, inclusive_size, *offset)?;
+
+        Ok((ribbon_emitter, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for RibbonEmitter {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
+
+        src.gwrite_with::<Node>(self.node, offset, ctx)?;
+
+        src.gwrite_with::<f32>(self.height_above, offset, ctx)?;
+        src.gwrite_with::<f32>(self.height_below, offset, ctx)?;
+        src.gwrite_with::<f32>(self.alpha, offset, ctx)?;
+        src.gwrite_with::<Color>(self.color, offset, ctx)?;
+        src.gwrite_with::<f32>(self.life_span, offset, ctx)?;
+        src.gwrite_with::<u32>(self.texture_slot, offset, ctx)?;
+        src.gwrite_with::<u32>(self.emission_rate, offset, ctx)?;
+        src.gwrite_with::<u32>(self.rows, offset, ctx)?;
+        src.gwrite_with::<u32>(self.columns, offset, ctx)?;
+        src.gwrite_with::<u32>(self.material_id, offset, ctx)?;
+        src.gwrite_with::<f32>(self.gravity, offset, ctx)?;
+
+        if let Some(height_above_transform) = self.height_above_transform {
+            src.gwrite_with::<u32>(KRHA_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(height_above_transform, offset, ctx)?;
+        }
+        if let Some(height_below_transform) = self.height_below_transform {
+            src.gwrite_with::<u32>(KRHB_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(height_below_transform, offset, ctx)?;
+        }
+        if let Some(alpha_transform) = self.alpha_transform {
+            src.gwrite_with::<u32>(KRAL_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(alpha_transform, offset, ctx)?;
+        }
+        if let Some(color_transform) = self.color_transform {
+            src.gwrite_with::<u32>(KRCO_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<Color>>(color_transform, offset, ctx)?;
+        }
+        if let Some(texture_slot_transform) = self.texture_slot_transform {
+            src.gwrite_with::<u32>(KRTX_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<u32>>(texture_slot_transform, offset, ctx)?;
+        }
+        if let Some(visibility_transform) = self.visibility_transform {
+            src.gwrite_with::<u32>(KRVS_TAG, offset, ctx)?;
+            src.gwrite_with::<Transform<f32>>(visibility_transform, offset, ctx)?;
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for RibbonEmitter {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // inclusive_size
+        result += 4;
+
+        result += self.node.total_bytes_size();
+
+        result += size_of_val(&self.height_above);
+        result += size_of_val(&self.height_below);
+        result += size_of_val(&self.alpha);
+        result += self.color.total_bytes_size();
+        result += size_of_val(&self.life_span);
+        result += size_of_val(&self.texture_slot);
+        result += size_of_val(&self.emission_rate);
+        result += size_of_val(&self.rows);
+        result += size_of_val(&self.columns);
+        result += size_of_val(&self.material_id);
+        result += size_of_val(&self.gravity);
+
+        if let Some(height_above_transform) = &self.height_above_transform {
+            result += 4;
+            result += height_above_transform.total_bytes_size();
+        }
+        if let Some(height_below_transform) = &self.height_below_transform {
+            result += 4;
+            result += height_below_transform.total_bytes_size();
+        }
+        if let Some(alpha_transform) = &self.alpha_transform {
+            result += 4;
+            result += alpha_transform.total_bytes_size();
+        }
+        if let Some(color_transform) = &self.color_transform {
+            result += 4;
+            result += color_transform.total_bytes_size();
+        }
+        if let Some(texture_slot_transform) = &self.texture_slot_transform {
+            result += 4;
+            result += texture_slot_transform.total_bytes_size();
+        }
+        if let Some(visibility_transform) = &self.visibility_transform {
+            result += 4;
+            result += visibility_transform.total_bytes_size();
+        }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
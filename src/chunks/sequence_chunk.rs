@@ -1,20 +1,19 @@
 use crate::chunks::BytesTotalSize;
 use crate::chunks::Extent;
+use crate::error::{check_size, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct SequenceChunk {
-    pub chunk_size: u32,
-
     // chunk_size / 132
     pub data: Vec<Sequence>,
 }
 
-calculate_chunk_size_impl!(SequenceChunk);
+chunk_size_impl!(SequenceChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for SequenceChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -23,12 +22,15 @@ impl ctx::TryFromCtx<'_, Endian> for SequenceChunk {
         let mut data = Vec::new();
         if let Some(sequence_count) = u32::checked_div(chunk_size.clone(), 132) {
             for _ in 0..sequence_count {
-                let sequence = src.gread_with::<Sequence>(offset, ctx)?;
+                let sequence: Sequence =
+                    read_within(src, offset, ctx, || format!("sequence {}", data.len()))?;
                 data.push(sequence);
             }
         }
 
-        Ok((SequenceChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((SequenceChunk { data }, *offset))
     }
 }
 
@@ -38,7 +40,7 @@ impl ctx::TryIntoCtx<Endian> for SequenceChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for sequence in self.data {
             src.gwrite_with::<Sequence>(sequence, offset, ctx)?;
@@ -52,7 +54,8 @@ impl BytesTotalSize for SequenceChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for sequence in &self.data {
             result += sequence.total_bytes_size();
@@ -1,19 +1,17 @@
-use crate::chunks::{BytesTotalSize, Transform, Vec3, Vec4};
+use crate::chunks::{BytesTotalSize, Transform, UnknownTracks, Vec3, Vec4};
 use crate::consts::{KTAR_TAG, KTAS_TAG, KTAT_TAG};
+use crate::error::{check_size, read_at, read_within, FourCC, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
-use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct TextureAnimationChunk {
-    pub chunk_size: u32,
-
     pub data: Vec<TextureAnimation>,
 }
 
-calculate_chunk_size_impl!(TextureAnimationChunk);
+chunk_size_impl!(TextureAnimationChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for TextureAnimationChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -22,12 +20,17 @@ impl ctx::TryFromCtx<'_, Endian> for TextureAnimationChunk {
         let mut data = Vec::new();
         let mut total_size = 0u32;
         while total_size < chunk_size {
-            let texture_animation = src.gread_with::<TextureAnimation>(offset, ctx)?;
-            total_size += texture_animation.inclusive_size;
+            let start = *offset;
+            let texture_animation: TextureAnimation = read_within(src, offset, ctx, || {
+                format!("texture animation {}", data.len())
+            })?;
+            total_size += (*offset - start) as u32;
             data.push(texture_animation);
         }
 
-        Ok((TextureAnimationChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((TextureAnimationChunk { data }, *offset))
     }
 }
 
@@ -37,7 +40,7 @@ impl ctx::TryIntoCtx<Endian> for TextureAnimationChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for texture_animation in self.data {
             src.gwrite_with::<TextureAnimation>(texture_animation, offset, ctx)?;
@@ -51,7 +54,8 @@ impl BytesTotalSize for TextureAnimationChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for texture_animation in &self.data {
             result += texture_animation.total_bytes_size();
@@ -63,47 +67,54 @@ impl BytesTotalSize for TextureAnimationChunk {
 
 #[derive(PartialEq, Debug)]
 pub struct TextureAnimation {
-    pub inclusive_size: u32,
-
     pub translation: Option<Transform<Vec3>>,
     pub rotation: Option<Transform<Vec4>>,
     pub scaling: Option<Transform<Vec3>>,
+    pub unknown_tracks: Option<UnknownTracks>,
 }
 
+inclusive_size_impl!(TextureAnimation);
+
 impl ctx::TryFromCtx<'_, Endian> for TextureAnimation {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
 
-        let inclusive_size = src.gread_with::<u32>(offset, ctx).unwrap();
+        let inclusive_size = src.gread_with::<u32>(offset, ctx)?;
         let mut texture_animation = TextureAnimation {
-            inclusive_size,
             translation: None,
             rotation: None,
             scaling: None,
+            unknown_tracks: None,
         };
 
         while (*offset as u32) < inclusive_size {
-            let tag = src.gread_with::<u32>(offset, ctx).unwrap();
+            let tag = read_at::<u32, _>(src, offset, ctx)?;
 
             match tag {
                 KTAT_TAG => {
-                    let ktat = src.gread_with(offset, ctx)?;
+                    let ktat = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     texture_animation.translation = Some(ktat);
                 }
                 KTAR_TAG => {
-                    let ktar = src.gread_with(offset, ctx)?;
+                    let ktar = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     texture_animation.rotation = Some(ktar);
                 }
                 KTAS_TAG => {
-                    let ktas = src.gread_with(offset, ctx)?;
+                    let ktas = read_within(src, offset, ctx, || FourCC(tag).to_string())?;
                     texture_animation.scaling = Some(ktas);
                 }
-                _ => unreachable!(),
+                _ => {
+                    let unknown_tracks =
+                        UnknownTracks::read_to_end(src, offset, tag, inclusive_size)?;
+                    texture_animation.unknown_tracks = Some(unknown_tracks);
+                }
             }
         }
 
+        check_size(0, inclusive_size, *offset)?;
+
         Ok((texture_animation, *offset))
     }
 }
@@ -114,7 +125,7 @@ impl ctx::TryIntoCtx<Endian> for TextureAnimation {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.inclusive_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.inclusive_size(), offset, ctx)?;
 
         if self.translation.is_some() {
             src.gwrite_with::<u32>(KTAT_TAG, offset, ctx)?;
@@ -128,6 +139,10 @@ impl ctx::TryIntoCtx<Endian> for TextureAnimation {
             src.gwrite_with::<u32>(KTAS_TAG, offset, ctx)?;
             src.gwrite_with::<Transform<Vec3>>(self.scaling.unwrap(), offset, ctx)?;
         }
+        if let Some(unknown_tracks) = self.unknown_tracks {
+            src.gwrite_with::<u32>(unknown_tracks.tag, offset, ctx)?;
+            src.gwrite_with::<UnknownTracks>(unknown_tracks, offset, ctx)?;
+        }
 
         Ok(*offset)
     }
@@ -137,7 +152,8 @@ impl BytesTotalSize for TextureAnimation {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.inclusive_size);
+        // inclusive_size
+        result += 4;
 
         if self.translation.is_some() {
             result += 4;
@@ -151,6 +167,10 @@ impl BytesTotalSize for TextureAnimation {
             result += 4;
             result += self.scaling.as_ref().unwrap().total_bytes_size();
         }
+        if let Some(unknown_tracks) = self.unknown_tracks.as_ref() {
+            result += 4;
+            result += unknown_tracks.total_bytes_size();
+        }
 
         result
     }
@@ -1,19 +1,18 @@
 use crate::chunks::BytesTotalSize;
+use crate::error::{check_size, read_within, MdxError};
 use scroll::{ctx, Endian, Pread, Pwrite};
 use std::mem::size_of_val;
 
 #[derive(PartialEq, Debug)]
 pub struct TextureChunk {
-    pub chunk_size: u32,
-
     // chunk_size / 268
     pub data: Vec<Texture>,
 }
 
-calculate_chunk_size_impl!(TextureChunk);
+chunk_size_impl!(TextureChunk);
 
 impl ctx::TryFromCtx<'_, Endian> for TextureChunk {
-    type Error = scroll::Error;
+    type Error = MdxError;
 
     fn try_from_ctx(src: &[u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
         let offset = &mut 0;
@@ -22,12 +21,15 @@ impl ctx::TryFromCtx<'_, Endian> for TextureChunk {
         let mut data = Vec::new();
         if let Some(texture_count) = u32::checked_div(chunk_size.clone(), 268) {
             for _ in 0..texture_count {
-                let texture = src.gread_with::<Texture>(offset, ctx)?;
+                let texture: Texture =
+                    read_within(src, offset, ctx, || format!("texture {}", data.len()))?;
                 data.push(texture);
             }
         }
 
-        Ok((TextureChunk { chunk_size, data }, *offset))
+        check_size(0, chunk_size, *offset - 4)?;
+
+        Ok((TextureChunk { data }, *offset))
     }
 }
 
@@ -37,7 +39,7 @@ impl ctx::TryIntoCtx<Endian> for TextureChunk {
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.chunk_size, offset, ctx)?;
+        src.gwrite_with::<u32>(self.chunk_size(), offset, ctx)?;
 
         for texture in self.data {
             src.gwrite_with::<Texture>(texture, offset, ctx)?;
@@ -51,7 +53,8 @@ impl BytesTotalSize for TextureChunk {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.chunk_size);
+        // chunk_size
+        result += 4;
 
         for texture in &self.data {
             result += texture.total_bytes_size();
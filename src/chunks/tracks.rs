@@ -1,13 +1,59 @@
-use crate::chunks::BytesTotalSize;
+use crate::chunks::{BytesTotalSize, Color, GlobalSequence, Vec3, Vec4};
+use crate::error::{MdxError, MdxErrorKind};
 use scroll::{ctx, Endian, Pread, Pwrite};
-use std::mem::size_of_val;
+use std::mem::{size_of, size_of_val};
+use std::ops::RangeInclusive;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[repr(u32)]
+pub enum Interpolation {
+    DontInterp = 0,
+    Linear = 1,
+    Hermite = 2,
+    Bezier = 3,
+}
+
+impl Interpolation {
+    pub fn has_tangents(self) -> bool {
+        match self {
+            Interpolation::DontInterp | Interpolation::Linear => false,
+            Interpolation::Hermite | Interpolation::Bezier => true,
+        }
+    }
+
+    fn from_raw(interpolation_type: u32) -> Option<Interpolation> {
+        match interpolation_type {
+            0 => Some(Interpolation::DontInterp),
+            1 => Some(Interpolation::Linear),
+            2 => Some(Interpolation::Hermite),
+            3 => Some(Interpolation::Bezier),
+            _ => None,
+        }
+    }
+}
+
+// Index into the global sequences of the model
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct GlobalSequenceId(pub u32);
+
+impl GlobalSequenceId {
+    // Files store no global sequence as 0xFFFFFFFF
+    pub(crate) fn from_raw(global_sequence_id: u32) -> Option<GlobalSequenceId> {
+        match global_sequence_id {
+            0xFFFF_FFFF => None,
+            global_sequence_id => Some(GlobalSequenceId(global_sequence_id)),
+        }
+    }
+
+    pub(crate) fn to_raw(global_sequence_id: Option<GlobalSequenceId>) -> u32 {
+        global_sequence_id.map_or(0xFFFF_FFFF, |global_sequence_id| global_sequence_id.0)
+    }
+}
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Track<T> {
     pub time: u32,
     pub value: T,
-    pub in_tan: Option<T>,
-    pub out_tan: Option<T>,
 }
 
 impl<'a, T: 'a> ctx::TryFromCtx<'a, Endian> for Track<T>
@@ -22,19 +68,71 @@ where
         let time = src.gread_with::<u32>(offset, ctx)?;
         let value = src.gread_with::<T>(offset, ctx)?;
 
+        Ok((Track { time, value }, *offset))
+    }
+}
+
+impl<T> ctx::TryIntoCtx<Endian> for Track<T>
+where
+    T: ctx::TryIntoCtx<Endian, Error = scroll::Error>,
+{
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.time, offset, ctx)?;
+        src.gwrite_with::<T>(self.value, offset, ctx)?;
+
+        Ok(*offset)
+    }
+}
+
+impl<T> BytesTotalSize for Track<T> {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+        result += size_of_val(&self.time);
+        result += size_of_val(&self.value);
+        result
+    }
+}
+
+// Key of a hermite or bezier track, in_tan shapes the curve coming in and out_tan the one leaving.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TangentTrack<T> {
+    pub time: u32,
+    pub value: T,
+    pub in_tan: T,
+    pub out_tan: T,
+}
+
+impl<'a, T: 'a> ctx::TryFromCtx<'a, Endian> for TangentTrack<T>
+where
+    T: ctx::TryFromCtx<'a, Endian, Error = scroll::Error>,
+{
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &'a [u8], ctx: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+
+        let time = src.gread_with::<u32>(offset, ctx)?;
+        let value = src.gread_with::<T>(offset, ctx)?;
+        let in_tan = src.gread_with::<T>(offset, ctx)?;
+        let out_tan = src.gread_with::<T>(offset, ctx)?;
+
         Ok((
-            Track {
+            TangentTrack {
                 time,
                 value,
-                in_tan: None,
-                out_tan: None,
+                in_tan,
+                out_tan,
             },
             *offset,
         ))
     }
 }
 
-impl<T> ctx::TryIntoCtx<Endian> for Track<T>
+impl<T> ctx::TryIntoCtx<Endian> for TangentTrack<T>
 where
     T: ctx::TryIntoCtx<Endian, Error = scroll::Error>,
 {
@@ -45,41 +143,114 @@ where
 
         src.gwrite_with::<u32>(self.time, offset, ctx)?;
         src.gwrite_with::<T>(self.value, offset, ctx)?;
-
-        if self.in_tan.is_some() {
-            src.gwrite_with::<T>(self.in_tan.unwrap(), offset, ctx)?;
-        }
-
-        if self.out_tan.is_some() {
-            src.gwrite_with::<T>(self.out_tan.unwrap(), offset, ctx)?;
-        }
+        src.gwrite_with::<T>(self.in_tan, offset, ctx)?;
+        src.gwrite_with::<T>(self.out_tan, offset, ctx)?;
 
         Ok(*offset)
     }
 }
 
-impl<T> BytesTotalSize for Track<T> {
+impl<T> BytesTotalSize for TangentTrack<T> {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
         result += size_of_val(&self.time);
         result += size_of_val(&self.value);
-        if self.in_tan.is_some() {
-            result += size_of_val(self.in_tan.as_ref().unwrap());
+        result += size_of_val(&self.in_tan);
+        result += size_of_val(&self.out_tan);
+        result
+    }
+}
+
+// Keys of a transform, tangents are there exactly when the interpolation uses them.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Tracks<T> {
+    DontInterp(Vec<Track<T>>),
+    Linear(Vec<Track<T>>),
+    Hermite(Vec<TangentTrack<T>>),
+    Bezier(Vec<TangentTrack<T>>),
+}
+
+impl<T> Tracks<T> {
+    pub fn interpolation(&self) -> Interpolation {
+        match self {
+            Tracks::DontInterp(_) => Interpolation::DontInterp,
+            Tracks::Linear(_) => Interpolation::Linear,
+            Tracks::Hermite(_) => Interpolation::Hermite,
+            Tracks::Bezier(_) => Interpolation::Bezier,
         }
-        if self.out_tan.is_some() {
-            result += size_of_val(self.out_tan.as_ref().unwrap());
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => tracks.len(),
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => tracks.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn times(&self) -> Vec<u32> {
+        match self {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => {
+                tracks.iter().map(|track| track.time).collect()
+            }
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => {
+                tracks.iter().map(|track| track.time).collect()
+            }
+        }
+    }
+
+    fn value(&self, index: usize) -> &T {
+        match self {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => &tracks[index].value,
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => &tracks[index].value,
         }
-        result
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Transform<T> {
-    pub number_of_tracks: u32,
-    pub interpolation_type: u32,
-    pub global_sequence_id: u32,
+    pub global_sequence_id: Option<GlobalSequenceId>,
+    pub tracks: Tracks<T>,
+}
+
+impl<T> Transform<T> {
+    pub fn interpolation(&self) -> Interpolation {
+        self.tracks.interpolation()
+    }
+}
+
+fn read_tracks<'a, K>(
+    src: &'a [u8],
+    offset: &mut usize,
+    ctx: Endian,
+    count: u32,
+) -> Result<Vec<K>, scroll::Error>
+where
+    K: ctx::TryFromCtx<'a, Endian, Error = scroll::Error>,
+{
+    let mut tracks = Vec::new();
+    for _ in 0..count {
+        tracks.push(src.gread_with::<K>(offset, ctx)?);
+    }
+    Ok(tracks)
+}
 
-    pub data: Vec<Track<T>>,
+fn write_tracks<K>(
+    src: &mut [u8],
+    offset: &mut usize,
+    ctx: Endian,
+    tracks: Vec<K>,
+) -> Result<(), scroll::Error>
+where
+    K: ctx::TryIntoCtx<Endian, Error = scroll::Error>,
+{
+    for track in tracks {
+        src.gwrite_with::<K>(track, offset, ctx)?;
+    }
+    Ok(())
 }
 
 impl<'a, T: 'a> ctx::TryFromCtx<'a, Endian> for Transform<T>
@@ -95,26 +266,38 @@ where
         let interpolation_type = src.gread_with::<u32>(offset, ctx)?;
         let global_sequence_id = src.gread_with::<u32>(offset, ctx)?;
 
-        let mut data = Vec::new();
-        for _ in 0..number_of_tracks {
-            let mut track = src.gread_with::<Track<T>>(offset, ctx)?;
+        let interpolation = Interpolation::from_raw(interpolation_type).ok_or_else(|| {
+            scroll::Error::Custom(format!("unknown interpolation type {}", interpolation_type))
+        })?;
 
-            if interpolation_type > 1 {
-                let in_tan = src.gread_with::<T>(offset, ctx)?;
-                let out_tan = src.gread_with::<T>(offset, ctx)?;
+        // Time and value, tangents come on top for hermite and bezier
+        let values = if interpolation.has_tangents() { 3 } else { 1 };
+        let track_size = size_of::<u32>() + values * size_of::<T>();
+        let size = (number_of_tracks as usize).saturating_mul(track_size);
+        let len = src.len().saturating_sub(*offset);
+        if size > len {
+            return Err(scroll::Error::TooBig { size, len });
+        }
 
-                track.in_tan = Some(in_tan);
-                track.out_tan = Some(out_tan);
+        let tracks = match interpolation {
+            Interpolation::DontInterp => {
+                Tracks::DontInterp(read_tracks(src, offset, ctx, number_of_tracks)?)
             }
-            data.push(track);
-        }
+            Interpolation::Linear => {
+                Tracks::Linear(read_tracks(src, offset, ctx, number_of_tracks)?)
+            }
+            Interpolation::Hermite => {
+                Tracks::Hermite(read_tracks(src, offset, ctx, number_of_tracks)?)
+            }
+            Interpolation::Bezier => {
+                Tracks::Bezier(read_tracks(src, offset, ctx, number_of_tracks)?)
+            }
+        };
 
         Ok((
             Transform {
-                number_of_tracks,
-                interpolation_type,
-                global_sequence_id,
-                data,
+                global_sequence_id: GlobalSequenceId::from_raw(global_sequence_id),
+                tracks,
             },
             *offset,
         ))
@@ -130,12 +313,21 @@ where
     fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
         let offset = &mut 0;
 
-        src.gwrite_with::<u32>(self.number_of_tracks, offset, ctx)?;
-        src.gwrite_with::<u32>(self.interpolation_type, offset, ctx)?;
-        src.gwrite_with::<u32>(self.global_sequence_id, offset, ctx)?;
+        src.gwrite_with::<u32>(self.tracks.len() as u32, offset, ctx)?;
+        src.gwrite_with::<u32>(self.tracks.interpolation() as u32, offset, ctx)?;
+        src.gwrite_with::<u32>(
+            GlobalSequenceId::to_raw(self.global_sequence_id),
+            offset,
+            ctx,
+        )?;
 
-        for track in self.data {
-            src.gwrite_with::<Track<T>>(track, offset, ctx)?;
+        match self.tracks {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => {
+                write_tracks(src, offset, ctx, tracks)?
+            }
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => {
+                write_tracks(src, offset, ctx, tracks)?
+            }
         }
 
         Ok(*offset)
@@ -146,14 +338,300 @@ impl<T> BytesTotalSize for Transform<T> {
     fn total_bytes_size(&self) -> usize {
         let mut result = 0usize;
 
-        result += size_of_val(&self.number_of_tracks);
-        result += size_of_val(&self.interpolation_type);
-        result += size_of_val(&self.global_sequence_id);
+        // number_of_tracks
+        result += 4;
+        // interpolation_type
+        result += 4;
+        // global_sequence_id
+        result += 4;
 
-        for track in &self.data {
-            result += track.total_bytes_size();
+        match &self.tracks {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => {
+                result += tracks.iter().map(|t| t.total_bytes_size()).sum::<usize>()
+            }
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => {
+                result += tracks.iter().map(|t| t.total_bytes_size()).sum::<usize>()
+            }
         }
 
         result
     }
 }
+
+// Where a frame falls between the keys of a track
+enum KeyPosition {
+    // Before the first key or after the last
+    Hold(usize),
+    // Between two keys, with t from 0 at the first to 1 at the second
+    Between(usize, usize, f32),
+}
+
+fn key_position(times: &[u32], time: u32, start: u32, end: u32) -> Option<KeyPosition> {
+    let first = times.iter().position(|&key| key >= start)?;
+    let last = times.iter().rposition(|&key| key <= end)?;
+    if first > last {
+        return None;
+    }
+
+    match times[first..=last].iter().position(|&key| key > time) {
+        Some(0) => Some(KeyPosition::Hold(first)),
+        None => Some(KeyPosition::Hold(last)),
+        Some(next) => {
+            let (from, to) = (first + next - 1, first + next);
+            let t = (time - times[from]) as f32 / (times[to] - times[from]) as f32;
+            Some(KeyPosition::Between(from, to, t))
+        }
+    }
+}
+
+impl<T: Interpolate> Transform<T> {
+    // Value at a frame the way the game evaluates it. Tracks on a global sequence loop over its
+    // duration on their own and ignore sequence_interval, everything else is clamped to the
+    // interval, both ends included like a sequence's interval_start and interval_end. Before the
+    // first key of the interval the first value holds, after the last key the last one does. None
+    // if no key falls in the interval, the static value of the object applies then. Keys are
+    // expected in time order. Fails if the global sequence isn't in global_sequences.
+    pub fn sample(
+        &self,
+        time: u32,
+        sequence_interval: RangeInclusive<u32>,
+        global_sequences: &[GlobalSequence],
+    ) -> Result<Option<T>, MdxError> {
+        let (time, start, end) = match self.global_sequence_id {
+            Some(global_sequence_id) => {
+                let global_sequence = global_sequences
+                    .get(global_sequence_id.0 as usize)
+                    .ok_or_else(|| {
+                        MdxError::new(
+                            0,
+                            MdxErrorKind::InvalidValue(format!(
+                                "global sequence {} doesn't exist",
+                                global_sequence_id.0
+                            )),
+                        )
+                    })?;
+                let duration = global_sequence.duration;
+                (time.checked_rem(duration).unwrap_or(0), 0, duration)
+            }
+            None => (time, *sequence_interval.start(), *sequence_interval.end()),
+        };
+        let time = time.max(start).min(end);
+
+        let (from, to, t) = match key_position(&self.tracks.times(), time, start, end) {
+            None => return Ok(None),
+            Some(KeyPosition::Hold(index)) => return Ok(Some(self.tracks.value(index).clone())),
+            Some(KeyPosition::Between(from, to, t)) => (from, to, t),
+        };
+        Ok(Some(match &self.tracks {
+            Tracks::DontInterp(tracks) => tracks[from].value.clone(),
+            Tracks::Linear(tracks) => T::linear(&tracks[from].value, &tracks[to].value, t),
+            Tracks::Hermite(tracks) => {
+                let (from, to) = (&tracks[from], &tracks[to]);
+                T::hermite(&from.value, &from.out_tan, &to.in_tan, &to.value, t)
+            }
+            Tracks::Bezier(tracks) => {
+                let (from, to) = (&tracks[from], &tracks[to]);
+                T::bezier(&from.value, &from.out_tan, &to.in_tan, &to.value, t)
+            }
+        }))
+    }
+}
+
+// Blending between two keys, t goes from 0 at the first key to 1 at the second.
+// out_tan belongs to the first key and in_tan to the second.
+pub trait Interpolate: Clone {
+    fn linear(from: &Self, to: &Self, t: f32) -> Self;
+    fn hermite(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self;
+    fn bezier(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn linear(from: &Self, to: &Self, t: f32) -> Self {
+        from + (to - from) * t
+    }
+
+    fn hermite(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+        let t2 = t * t;
+        let t3 = t2 * t;
+        from * (2.0 * t3 - 3.0 * t2 + 1.0)
+            + out_tan * (t3 - 2.0 * t2 + t)
+            + in_tan * (t3 - t2)
+            + to * (3.0 * t2 - 2.0 * t3)
+    }
+
+    fn bezier(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+        let u = 1.0 - t;
+        from * u * u * u + out_tan * 3.0 * t * u * u + in_tan * 3.0 * t * t * u + to * t * t * t
+    }
+}
+
+// Ids like texture ids can't be blended, they step from key to key
+impl Interpolate for u32 {
+    fn linear(from: &Self, _to: &Self, _t: f32) -> Self {
+        *from
+    }
+
+    fn hermite(from: &Self, _out_tan: &Self, _in_tan: &Self, _to: &Self, _t: f32) -> Self {
+        *from
+    }
+
+    fn bezier(from: &Self, _out_tan: &Self, _in_tan: &Self, _to: &Self, _t: f32) -> Self {
+        *from
+    }
+}
+
+macro_rules! interpolate_impl {
+    ($name:ident { $($field:ident),* }) => {
+        impl Interpolate for $name {
+            fn linear(from: &Self, to: &Self, t: f32) -> Self {
+                $name {
+                    $($field: f32::linear(&from.$field, &to.$field, t)),*
+                }
+            }
+
+            fn hermite(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+                $name {
+                    $($field: f32::hermite(
+                        &from.$field,
+                        &out_tan.$field,
+                        &in_tan.$field,
+                        &to.$field,
+                        t,
+                    )),*
+                }
+            }
+
+            fn bezier(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+                $name {
+                    $($field: f32::bezier(
+                        &from.$field,
+                        &out_tan.$field,
+                        &in_tan.$field,
+                        &to.$field,
+                        t,
+                    )),*
+                }
+            }
+        }
+    };
+}
+
+interpolate_impl!(Vec3 { x, y, z });
+interpolate_impl!(Color { b, g, r });
+
+// Vec4 tracks are rotations, so they're treated as quaternions: linear keys slerp, hermite
+// and bezier keys both squad through the tangents, and results come out normalised.
+impl Interpolate for Vec4 {
+    fn linear(from: &Self, to: &Self, t: f32) -> Self {
+        slerp(from, to, t)
+    }
+
+    fn hermite(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+        squad(from, out_tan, in_tan, to, t)
+    }
+
+    fn bezier(from: &Self, out_tan: &Self, in_tan: &Self, to: &Self, t: f32) -> Self {
+        squad(from, out_tan, in_tan, to, t)
+    }
+}
+
+fn squad(from: &Vec4, out_tan: &Vec4, in_tan: &Vec4, to: &Vec4, t: f32) -> Vec4 {
+    let keys = slerp(from, to, t);
+    let tangents = slerp(out_tan, in_tan, t);
+    slerp(&keys, &tangents, 2.0 * t * (1.0 - t))
+}
+
+fn slerp(from: &Vec4, to: &Vec4, t: f32) -> Vec4 {
+    let mut to = to.clone();
+    let mut cos = from.x * to.x + from.y * to.y + from.z * to.z + from.w * to.w;
+    // Shortest way around
+    if cos < 0.0 {
+        cos = -cos;
+        to = Vec4 {
+            x: -to.x,
+            y: -to.y,
+            z: -to.z,
+            w: -to.w,
+        };
+    }
+
+    let (scale_from, scale_to) = if cos < 0.9999 {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    } else {
+        // Nearly the same rotation, lerp avoids dividing by a tiny sine
+        (1.0 - t, t)
+    };
+
+    normalize(Vec4 {
+        x: from.x * scale_from + to.x * scale_to,
+        y: from.y * scale_from + to.y * scale_to,
+        z: from.z * scale_from + to.z * scale_to,
+        w: from.w * scale_from + to.w * scale_to,
+    })
+}
+
+fn normalize(q: Vec4) -> Vec4 {
+    let length = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+    if length == 0.0 {
+        return q;
+    }
+    Vec4 {
+        x: q.x / length,
+        y: q.y / length,
+        z: q.z / length,
+        w: q.w / length,
+    }
+}
+
+// Animated block with a tag we don't know, e.g. from newer or modded files.
+// Its size depends on the value type, so where it ends can't be told. Everything
+// from its tag to the end of the owning object is kept as raw bytes, including any
+// known blocks after it, which are then left undecoded. The bytes are written back
+// after the known blocks.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnknownTracks {
+    pub tag: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl UnknownTracks {
+    // Reads from offset (just after the tag) up to the end of the owning object.
+    pub(crate) fn read_to_end(
+        src: &[u8],
+        offset: &mut usize,
+        tag: u32,
+        inclusive_size: u32,
+    ) -> Result<Self, MdxError> {
+        // Tag may already run past the object, size check of the owner reports that
+        let end = (inclusive_size as usize).max(*offset);
+        let bytes = src
+            .get(*offset..end)
+            .ok_or_else(|| MdxError::new(*offset, MdxErrorKind::UnexpectedEof))?
+            .to_vec();
+        *offset = end;
+
+        Ok(UnknownTracks { tag, bytes })
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for UnknownTracks {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], _ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        if !self.bytes.is_empty() {
+            src.gwrite_with::<&[u8]>(&self.bytes, offset, ())?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for UnknownTracks {
+    fn total_bytes_size(&self) -> usize {
+        self.bytes.len()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::chunks::BytesTotalSize;
+use scroll::{ctx, Endian, Pread, Pwrite};
+use std::mem::size_of_val;
+
+// Chunk with a tag we don't parse, e.g. newer Reforged chunks or custom tool data.
+// Kept as raw bytes so it can be written back unchanged.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnknownChunk {
+    pub tag: u32,
+    pub bytes: Vec<u8>,
+}
+
+// Tag is read by the caller, so it comes in with the context.
+impl ctx::TryFromCtx<'_, (u32, Endian)> for UnknownChunk {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &[u8], (tag, ctx): (u32, Endian)) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let chunk_size = src.gread_with::<u32>(offset, ctx)?;
+        // Empty payload may sit at the very end of the file, where gread would fail
+        let bytes = src
+            .get(*offset..)
+            .and_then(|rest| rest.get(..chunk_size as usize))
+            .ok_or(scroll::Error::TooBig {
+                size: chunk_size as usize,
+                len: src.len() - *offset,
+            })?
+            .to_vec();
+        *offset += bytes.len();
+
+        Ok((UnknownChunk { tag, bytes }, *offset))
+    }
+}
+
+impl ctx::TryIntoCtx<Endian> for UnknownChunk {
+    type Error = scroll::Error;
+
+    fn try_into_ctx(self, src: &mut [u8], ctx: Endian) -> Result<usize, Self::Error> {
+        let offset = &mut 0;
+
+        src.gwrite_with::<u32>(self.bytes.len() as u32, offset, ctx)?;
+        if !self.bytes.is_empty() {
+            src.gwrite_with::<&[u8]>(&self.bytes, offset, ())?;
+        }
+
+        Ok(*offset)
+    }
+}
+
+impl BytesTotalSize for UnknownChunk {
+    fn total_bytes_size(&self) -> usize {
+        let mut result = 0usize;
+
+        // chunk_size
+        result += size_of_val(&(self.bytes.len() as u32));
+        result += self.bytes.len();
+
+        result
+    }
+}
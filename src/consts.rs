@@ -21,12 +21,19 @@ pub const RIBB_TAG: u32 = 1111640402;
 pub const EVTS_TAG: u32 = 1398036037;
 pub const CAMS_TAG: u32 = 1397571907;
 pub const CLID_TAG: u32 = 1145654339;
+pub const CORN_TAG: u32 = 1314017091;
+pub const FAFX_TAG: u32 = 1480999238;
+pub const BPOS_TAG: u32 = 1397706818;
+pub const TXST_TAG: u32 = 1414748244;
 
 // Node
 pub const KGTR_TAG: u32 = 1381254987;
 pub const KGRT_TAG: u32 = 1414678347;
 pub const KGSC_TAG: u32 = 1129531211;
 
+// Material
+pub const LAYS_TAG: u32 = 1398358348;
+
 // Layer
 pub const KMTF_TAG: u32 = 1179929931;
 pub const KMTA_TAG: u32 = 1096043851;
@@ -83,6 +90,9 @@ pub const KRAL_TAG: u32 = 1279349323;
 pub const KRCO_TAG: u32 = 1329812043;
 pub const KRTX_TAG: u32 = 1481921099;
 
+// Event object
+pub const KEVT_TAG: u32 = 1414939979;
+
 // Camera
 pub const KCTR_TAG: u32 = 1381253963;
 pub const KCRL_TAG: u32 = 1280459595;
@@ -107,3 +117,5 @@ pub const MTGC_TAG: u32 = 1128748109;
 pub const MATS_TAG: u32 = 1398030669;
 pub const UVAS_TAG: u32 = 1396790869;
 pub const UVBS_TAG: u32 = 1396856405;
+pub const TANG_TAG: u32 = 1196310868;
+pub const SKIN_TAG: u32 = 1313426259;
@@ -0,0 +1,194 @@
+use scroll::{ctx, Endian, Pread};
+use std::fmt;
+
+// Chunk tag shown as its four characters, e.g. GEOS
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct FourCC(pub u32);
+
+impl fmt::Display for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = self.0.to_le_bytes();
+        if bytes.iter().all(|b| b.is_ascii_graphic()) {
+            for b in &bytes {
+                write!(f, "{}", *b as char)?;
+            }
+            Ok(())
+        } else {
+            write!(f, "0x{:08X}", self.0)
+        }
+    }
+}
+
+impl fmt::Debug for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FourCC({})", self)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum MdxErrorKind {
+    NotMdx,
+    UnexpectedEof,
+    UnexpectedTag { expected: FourCC, found: FourCC },
+    SizeMismatch { expected: u32, found: u32 },
+    InvalidValue(String),
+    Io(String),
+}
+
+impl fmt::Display for MdxErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MdxErrorKind::NotMdx => write!(f, "not an MDLX file"),
+            MdxErrorKind::UnexpectedEof => write!(f, "unexpected end of data"),
+            MdxErrorKind::UnexpectedTag { expected, found } => {
+                write!(f, "expected tag {} - found {}", expected, found)
+            }
+            MdxErrorKind::SizeMismatch { expected, found } => {
+                write!(f, "size mismatch, expected {} - found {}", expected, found)
+            }
+            MdxErrorKind::InvalidValue(message) => write!(f, "{}", message),
+            MdxErrorKind::Io(message) => write!(f, "io error: {}", message),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct MdxError {
+    // Byte offset in the file where reading failed
+    pub offset: usize,
+    // Top level chunk the failure happened in
+    pub tag: Option<FourCC>,
+    // Nested structures from outer to inner, e.g. ["geoset 3", "UVBS 1"]
+    pub path: Vec<String>,
+    pub kind: MdxErrorKind,
+}
+
+impl MdxError {
+    pub fn new(offset: usize, kind: MdxErrorKind) -> Self {
+        MdxError {
+            offset,
+            tag: None,
+            path: Vec::new(),
+            kind,
+        }
+    }
+
+    // Readers see their own slice, so offsets are shifted by every parent on the way up.
+    pub(crate) fn offset_by(mut self, offset: usize) -> Self {
+        self.offset += offset;
+        self
+    }
+
+    pub(crate) fn within<S: Into<String>>(self, offset: usize, name: S) -> Self {
+        let mut error = self.offset_by(offset);
+        error.path.insert(0, name.into());
+        error
+    }
+
+    pub(crate) fn in_chunk(self, offset: usize, tag: u32) -> Self {
+        let mut error = self.offset_by(offset);
+        error.tag = Some(FourCC(tag));
+        error
+    }
+}
+
+impl From<scroll::Error> for MdxError {
+    fn from(error: scroll::Error) -> Self {
+        match error {
+            scroll::Error::TooBig { .. } | scroll::Error::BadOffset(_) => {
+                MdxError::new(0, MdxErrorKind::UnexpectedEof)
+            }
+            scroll::Error::BadInput { msg, .. } => {
+                MdxError::new(0, MdxErrorKind::InvalidValue(msg.to_string()))
+            }
+            scroll::Error::Custom(message) => MdxError::new(0, MdxErrorKind::InvalidValue(message)),
+            scroll::Error::IO(error) => MdxError::from(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for MdxError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => MdxError::new(0, MdxErrorKind::UnexpectedEof),
+            _ => MdxError::new(0, MdxErrorKind::Io(error.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for MdxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)?;
+        if let Some(tag) = self.tag {
+            write!(f, " in {}", tag)?;
+        }
+        for name in &self.path {
+            write!(f, " -> {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MdxError {}
+
+// Reads a nested structure and names it in the error path if it fails.
+pub(crate) fn read_within<'a, T, C, E, F>(
+    src: &'a [u8],
+    offset: &mut usize,
+    ctx: C,
+    name: F,
+) -> Result<T, MdxError>
+where
+    T: ctx::TryFromCtx<'a, C, Error = E>,
+    C: Copy,
+    E: From<scroll::Error> + Into<MdxError>,
+    F: FnOnce() -> String,
+{
+    let start = *offset;
+    src.gread_with::<T>(offset, ctx)
+        .map_err(|error| error.into().within(start, name()))
+}
+
+// Reads a value, keeping the position it was read from.
+pub(crate) fn read_at<'a, T, E>(
+    src: &'a [u8],
+    offset: &mut usize,
+    ctx: Endian,
+) -> Result<T, MdxError>
+where
+    T: ctx::TryFromCtx<'a, Endian, Error = E>,
+    E: From<scroll::Error> + Into<MdxError>,
+{
+    let start = *offset;
+    src.gread_with::<T>(offset, ctx)
+        .map_err(|error| error.into().offset_by(start))
+}
+
+pub(crate) fn check_size(offset: usize, expected: u32, found: usize) -> Result<(), MdxError> {
+    if expected as usize != found {
+        return Err(MdxError::new(
+            offset,
+            MdxErrorKind::SizeMismatch {
+                expected,
+                found: found as u32,
+            },
+        ));
+    }
+    Ok(())
+}
+
+// Counts come from the file, so one can't promise more items than the bytes left can hold.
+pub(crate) fn read_count(
+    src: &[u8],
+    offset: &mut usize,
+    ctx: Endian,
+    item_size: usize,
+) -> Result<u32, scroll::Error> {
+    let count = src.gread_with::<u32>(offset, ctx)?;
+    let size = (count as usize).saturating_mul(item_size);
+    let len = src.len().saturating_sub(*offset);
+    if size > len {
+        return Err(scroll::Error::TooBig { size, len });
+    }
+    Ok(count)
+}
@@ -0,0 +1,131 @@
+use crate::chunks::*;
+use crate::consts::*;
+use crate::error::{read_at, MdxError, MdxErrorKind};
+use scroll::{ctx, Endian, Pread, LE};
+
+// Where a top level chunk sits in the file
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ChunkEntry {
+    pub tag: u32,
+    // Offset of the tag
+    pub offset: usize,
+    // chunk_size, without the tag and the size itself
+    pub size: usize,
+}
+
+macro_rules! index_chunk_impl {
+    ($name:ident, $tag:ident, $chunk:ident) => {
+        pub fn $name(&self) -> Result<Option<$chunk>, MdxError> {
+            self.chunk::<$chunk, _, _>($tag, LE)
+        }
+    };
+}
+
+// Top level chunks of an mdx file, each one decoded only when asked for.
+#[derive(Debug, Clone)]
+pub struct MdxIndex<'a> {
+    data: &'a [u8],
+    entries: Vec<ChunkEntry>,
+}
+
+impl<'a> MdxIndex<'a> {
+    pub fn new(data: &'a [u8]) -> Result<MdxIndex<'a>, MdxError> {
+        let offset = &mut 0usize;
+        let mdlx_tag = read_at::<u32, _>(data, offset, LE)?;
+        if mdlx_tag != MDLX_TAG {
+            return Err(MdxError::new(0, MdxErrorKind::NotMdx));
+        }
+
+        let mut entries = Vec::new();
+        while *offset < data.len() {
+            let start = *offset;
+            let tag = read_at::<u32, _>(data, offset, LE)?;
+            let size = read_at::<u32, _>(data, offset, LE).map_err(|e| e.in_chunk(0, tag))?;
+
+            let size = size as usize;
+            if data.len() - *offset < size {
+                return Err(MdxError::new(data.len(), MdxErrorKind::UnexpectedEof).in_chunk(0, tag));
+            }
+            *offset += size;
+
+            entries.push(ChunkEntry {
+                tag,
+                offset: start,
+                size,
+            });
+        }
+
+        Ok(MdxIndex { data, entries })
+    }
+
+    pub fn entries(&self) -> &[ChunkEntry] {
+        &self.entries
+    }
+
+    // First chunk with the tag
+    pub fn entry(&self, tag: u32) -> Option<&ChunkEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+
+    // Chunk bytes starting at chunk_size, the way chunk readers expect them.
+    pub fn chunk_bytes(&self, tag: u32) -> Option<&'a [u8]> {
+        self.entry(tag).map(|entry| self.entry_bytes(entry))
+    }
+
+    fn entry_bytes(&self, entry: &ChunkEntry) -> &'a [u8] {
+        &self.data[entry.offset + 4..entry.offset + 8 + entry.size]
+    }
+
+    pub fn chunk<T, C, E>(&self, tag: u32, ctx: C) -> Result<Option<T>, MdxError>
+    where
+        T: ctx::TryFromCtx<'a, C, Error = E>,
+        C: Copy,
+        E: From<scroll::Error> + Into<MdxError>,
+    {
+        let entry = match self.entry(tag) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        self.entry_bytes(entry)
+            .pread_with::<T>(0, ctx)
+            .map(Some)
+            .map_err(|e| e.into().in_chunk(entry.offset + 4, tag))
+    }
+
+    // Format version from VERS, files without it are treated as 800
+    pub fn version(&self) -> Result<u32, MdxError> {
+        let version_chunk = self.chunk::<VersionChunk, Endian, _>(VERS_TAG, LE)?;
+        Ok(version_chunk.map_or(800, |version_chunk| version_chunk.version))
+    }
+
+    pub fn geosets(&self) -> Result<Option<GeosetChunk>, MdxError> {
+        let version = self.version()?;
+        self.chunk::<GeosetChunk, _, _>(GEOS_TAG, (LE, version))
+    }
+
+    pub fn materials(&self) -> Result<Option<MaterialChunk>, MdxError> {
+        let version = self.version()?;
+        self.chunk::<MaterialChunk, _, _>(MTLS_TAG, (LE, version))
+    }
+
+    index_chunk_impl!(model, MODL_TAG, ModelChunk);
+    index_chunk_impl!(sequences, SEQS_TAG, SequenceChunk);
+    index_chunk_impl!(global_sequences, GLBS_TAG, GlobalSequenceChunk);
+    index_chunk_impl!(textures, TEXS_TAG, TextureChunk);
+    index_chunk_impl!(texture_animations, TXAN_TAG, TextureAnimationChunk);
+    index_chunk_impl!(geoset_animations, GEOA_TAG, GeosetAnimationChunk);
+    index_chunk_impl!(bones, BONE_TAG, BoneChunk);
+    index_chunk_impl!(lights, LITE_TAG, LightChunk);
+    index_chunk_impl!(helpers, HELP_TAG, HelperChunk);
+    index_chunk_impl!(attachments, ATCH_TAG, AttachmentChunk);
+    index_chunk_impl!(pivot_points, PIVT_TAG, PivotPointChunk);
+    index_chunk_impl!(particle_emitters, PREM_TAG, ParticleEmitterChunk);
+    index_chunk_impl!(particle_emitters2, PRE2_TAG, ParticleEmitter2Chunk);
+    index_chunk_impl!(popcorn_emitters, CORN_TAG, PopcornEmitterChunk);
+    index_chunk_impl!(ribbon_emitters, RIBB_TAG, RibbonEmitterChunk);
+    index_chunk_impl!(cameras, CAMS_TAG, CameraChunk);
+    index_chunk_impl!(event_objects, EVTS_TAG, EventObjectChunk);
+    index_chunk_impl!(collision_shapes, CLID_TAG, CollisionShapeChunk);
+    index_chunk_impl!(face_effects, FAFX_TAG, FaceEffectChunk);
+    index_chunk_impl!(bind_pose, BPOS_TAG, BindPoseChunk);
+}
@@ -1,15 +1,24 @@
 #[macro_use]
 extern crate log;
 
+pub use builder::{Mesh, ModelBuilder};
+pub use error::{FourCC, MdxError, MdxErrorKind};
+pub use index::{ChunkEntry, MdxIndex};
 pub use mdlx::MDLXModel;
 
+mod builder;
 pub mod chunks;
 pub mod consts;
+mod error;
+mod index;
+pub mod mdl;
 mod mdlx;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunks::*;
+    use crate::consts::*;
     use std::fs;
 
     fn init() {
@@ -28,4 +37,2073 @@ mod tests {
         dbg!(&bytes.len());
         fs::write("testfiles/resave.mdx", bytes).unwrap();
     }
+
+    fn resave(model: MDLXModel) -> (Vec<u8>, MDLXModel) {
+        let bytes = MDLXModel::write_mdx_file(model).unwrap();
+        let model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+        (bytes, model)
+    }
+
+    // MDL doesn't keep the chunk order, so it's taken from the model read from mdx
+    fn reparse_mdl(text: &str, model: &MDLXModel) -> MDLXModel {
+        let mut reparsed = MDLXModel::read_mdl_file(text).unwrap();
+        reparsed.chunk_order = model.chunk_order.clone();
+        reparsed
+    }
+
+    // Little endian bytes of the words, for hand built chunk fixtures
+    fn words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    // Chunk with the tag, from the tag up to the given length
+    fn chunk(bytes: &[u8], tag: u32, len: usize) -> &[u8] {
+        let start = bytes
+            .windows(4)
+            .position(|window| window == tag.to_le_bytes())
+            .unwrap();
+        &bytes[start..start + len]
+    }
+
+    // Bytes of node(name, object_id)
+    fn node_bytes(name: &str, object_id: u32) -> Vec<u8> {
+        let mut bytes = words(&[96]);
+        bytes.extend(name.as_bytes());
+        bytes.resize(4 + 80, 0);
+        bytes.extend(words(&[object_id, 0xFFFF_FFFF, 0]));
+        bytes
+    }
+
+    fn node(name: &str, object_id: u32) -> Node {
+        Node {
+            name: name.to_string(),
+            object_id,
+            parent_id: 0xFFFF_FFFF,
+            flags: 0,
+            translation: None,
+            rotation: None,
+            scaling: None,
+            unknown_tracks: None,
+        }
+    }
+
+    #[test]
+    fn material_chunk_round_trip() {
+        init();
+
+        let alpha_transform = Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Hermite(vec![
+                TangentTrack {
+                    time: 0,
+                    value: 0.0,
+                    in_tan: 0.0,
+                    out_tan: 0.0,
+                },
+                TangentTrack {
+                    time: 100,
+                    value: 1.0,
+                    in_tan: 1.0,
+                    out_tan: 1.0,
+                },
+            ]),
+        };
+        let layer = Layer {
+            filter_mode: 2,
+            shading_flags: 0x10,
+            texture_id: 1,
+            texture_animation_id: 0xFFFF_FFFF,
+            coord_id: 0,
+            alpha: 0.5,
+            hd: None,
+            unknown_bytes: None,
+            texture_id_transform: None,
+            alpha_transform: Some(alpha_transform.clone()),
+            emissive_gain_transform: None,
+            fresnel_color_transform: None,
+            fresnel_alpha_transform: None,
+            fresnel_team_color_transform: None,
+            unknown_tracks: None,
+        };
+
+        let material = Material {
+            priority_plane: 0,
+            flags: 0,
+            shader: None,
+            layers: vec![layer],
+        };
+
+        let model = MDLXModel {
+            material_chunk: Some(MaterialChunk {
+                data: vec![material],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let material_chunk = model.material_chunk.as_ref().unwrap();
+        assert_eq!(material_chunk.data[0].layers[0].texture_id, 1);
+        assert_eq!(material_chunk.data[0].layers[0].filter_mode, 2);
+        assert_eq!(
+            material_chunk.data[0].layers[0].alpha_transform,
+            Some(alpha_transform)
+        );
+
+        // Version 800 layout: material, LAYS, then the layer with its KMTA block
+        #[rustfmt::skip]
+        let expected = words(&[
+            MTLS_TAG, 96,
+            96, 0, 0, LAYS_TAG, 1,
+            76, 2, 0x10, 1, 0xFFFF_FFFF, 0, 0.5f32.to_bits(),
+            KMTA_TAG, 2, 2, 0xFFFF_FFFF,
+            0, 0, 0, 0,
+            100, 1.0f32.to_bits(), 1.0f32.to_bits(), 1.0f32.to_bits(),
+        ]);
+        assert_eq!(chunk(&bytes, MTLS_TAG, expected.len()), &expected[..]);
+        let read = MDLXModel::read_mdx_file([&b"MDLX"[..], &expected].concat()).unwrap();
+        assert_eq!(read.material_chunk, model.material_chunk);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn particle_emitter_chunk_round_trip() {
+        init();
+
+        let linear = |value: f32| Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Linear(vec![
+                Track { time: 0, value },
+                Track {
+                    time: 1000,
+                    value: value * 2.0,
+                },
+            ]),
+        };
+        let tangents = |value: f32| {
+            vec![TangentTrack {
+                time: 500,
+                value,
+                in_tan: 0.5,
+                out_tan: -0.5,
+            }]
+        };
+        let step = |global_sequence_id, value: f32| Transform {
+            global_sequence_id,
+            tracks: Tracks::DontInterp(vec![Track { time: 0, value }]),
+        };
+        // The longest path that still leaves room for the terminating zero
+        let path = format!("Abilities\\{}.mdl", "x".repeat(259 - 14));
+        assert_eq!(path.len(), 259);
+
+        let particle_emitter = ParticleEmitter {
+            node: Node {
+                flags: 0x1000,
+                ..node("Emitter01", 0)
+            },
+            emission_rate: 10.0,
+            gravity: -9.8,
+            longitude: 0.5,
+            latitude: 0.25,
+            path: path.clone(),
+            life_span: 2.0,
+            speed: 100.0,
+            emission_rate_transform: Some(linear(10.0)),
+            gravity_transform: Some(step(None, -9.8)),
+            longitude_transform: Some(linear(0.5)),
+            latitude_transform: Some(linear(0.25)),
+            life_span_transform: Some(Transform {
+                global_sequence_id: None,
+                tracks: Tracks::Hermite(tangents(2.0)),
+            }),
+            speed_transform: Some(Transform {
+                global_sequence_id: None,
+                tracks: Tracks::Bezier(tangents(100.0)),
+            }),
+            visibility_transform: Some(step(Some(GlobalSequenceId(0)), 1.0)),
+            unknown_tracks: None,
+        };
+
+        let model = MDLXModel {
+            particle_emitter_chunk: Some(ParticleEmitterChunk {
+                data: vec![particle_emitter],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let particle_emitter = &model.particle_emitter_chunk.as_ref().unwrap().data[0];
+        assert_eq!(particle_emitter.path, path);
+        assert_eq!(particle_emitter.gravity, -9.8);
+        assert_eq!(particle_emitter.speed, 100.0);
+        assert_eq!(
+            particle_emitter
+                .life_span_transform
+                .as_ref()
+                .unwrap()
+                .tracks,
+            Tracks::Hermite(tangents(2.0))
+        );
+        assert_eq!(
+            particle_emitter.speed_transform.as_ref().unwrap().tracks,
+            Tracks::Bezier(tangents(100.0))
+        );
+        assert_eq!(
+            particle_emitter.visibility_transform,
+            Some(step(Some(GlobalSequenceId(0)), 1.0))
+        );
+
+        // The path takes its whole 260 bytes, zero padded, and the tracks follow life span and speed
+        let start = bytes
+            .windows(path.len())
+            .position(|window| window == path.as_bytes())
+            .unwrap();
+        assert_eq!(bytes[start + path.len()..start + 260], [0]);
+        assert_eq!(&bytes[start + 260 + 8..start + 260 + 12], b"KPEE");
+
+        let (resaved_bytes, model) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // MDL keeps the path and tracks, but not the static values of animated properties
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        let mut reparsed = reparse_mdl(&written, &model);
+        let particle_emitter = &model.particle_emitter_chunk.as_ref().unwrap().data[0];
+        let reparsed_emitter = &mut reparsed.particle_emitter_chunk.as_mut().unwrap().data[0];
+        reparsed_emitter.emission_rate = particle_emitter.emission_rate;
+        reparsed_emitter.gravity = particle_emitter.gravity;
+        reparsed_emitter.longitude = particle_emitter.longitude;
+        reparsed_emitter.latitude = particle_emitter.latitude;
+        reparsed_emitter.life_span = particle_emitter.life_span;
+        reparsed_emitter.speed = particle_emitter.speed;
+        assert_eq!(reparsed_emitter, particle_emitter);
+    }
+
+    #[test]
+    fn ribbon_emitter_chunk_round_trip() {
+        init();
+
+        let color = |v: f32| Color {
+            b: v,
+            g: 0.5,
+            r: 1.0 - v,
+        };
+        let ribbon_emitter = RibbonEmitter {
+            node: Node {
+                flags: 0x4000,
+                ..node("Ribbon01", 0)
+            },
+            height_above: 20.0,
+            height_below: 10.0,
+            alpha: 0.75,
+            color: color(1.0),
+            life_span: 0.5,
+            texture_slot: 2,
+            emission_rate: 30,
+            rows: 2,
+            columns: 4,
+            material_id: 1,
+            gravity: 5.0,
+            height_above_transform: None,
+            height_below_transform: None,
+            alpha_transform: None,
+            color_transform: Some(Transform {
+                global_sequence_id: None,
+                tracks: Tracks::Linear(vec![
+                    Track {
+                        time: 0,
+                        value: color(0.0),
+                    },
+                    Track {
+                        time: 1000,
+                        value: color(1.0),
+                    },
+                ]),
+            }),
+            texture_slot_transform: Some(Transform {
+                global_sequence_id: Some(GlobalSequenceId(0)),
+                tracks: Tracks::DontInterp(vec![
+                    Track { time: 0, value: 0 },
+                    Track {
+                        time: 250,
+                        value: 7,
+                    },
+                ]),
+            }),
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        let model = MDLXModel {
+            ribbon_emitter_chunk: Some(RibbonEmitterChunk {
+                data: vec![ribbon_emitter],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let ribbon_emitter = &model.ribbon_emitter_chunk.as_ref().unwrap().data[0];
+        assert_eq!(ribbon_emitter.texture_slot, 2);
+        assert_eq!(ribbon_emitter.columns, 4);
+        let color_transform = ribbon_emitter.color_transform.as_ref().unwrap();
+        assert_eq!(color_transform.interpolation(), Interpolation::Linear);
+        assert_eq!(color_transform.tracks.len(), 2);
+        let texture_slot_transform = ribbon_emitter.texture_slot_transform.as_ref().unwrap();
+        assert_eq!(
+            texture_slot_transform.global_sequence_id,
+            Some(GlobalSequenceId(0))
+        );
+        assert_eq!(
+            texture_slot_transform.tracks,
+            Tracks::DontInterp(vec![
+                Track { time: 0, value: 0 },
+                Track {
+                    time: 250,
+                    value: 7
+                },
+            ])
+        );
+
+        // KRCO: count, linear, no global sequence, then time and blue, green, red per key
+        let words = |tag: &[u8], count: usize| {
+            let start = bytes.windows(4).position(|window| window == tag).unwrap() + 4;
+            bytes[start..start + count * 4]
+                .chunks(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(
+            words(b"KRCO", 7),
+            vec![2, 1, 0xFFFF_FFFF, 0, 0, 0.5f32.to_bits(), 1.0f32.to_bits()]
+        );
+        // KRTX: stepped texture slots on global sequence 0
+        assert_eq!(words(b"KRTX", 7), vec![2, 0, 0, 0, 0, 250, 7]);
+
+        let (resaved_bytes, model) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // MDL keeps the tracks, but not the static values of animated properties
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        let mut reparsed = reparse_mdl(&written, &model);
+        let ribbon_emitter = &model.ribbon_emitter_chunk.as_ref().unwrap().data[0];
+        let reparsed_emitter = &mut reparsed.ribbon_emitter_chunk.as_mut().unwrap().data[0];
+        reparsed_emitter.color = ribbon_emitter.color.clone();
+        reparsed_emitter.texture_slot = ribbon_emitter.texture_slot;
+        assert_eq!(reparsed_emitter, ribbon_emitter);
+    }
+
+    #[test]
+    fn particle_emitter2_chunk_round_trip() {
+        init();
+
+        let color = |v: f32| Color { b: v, g: v, r: v };
+        let particle_emitter2 = ParticleEmitter2 {
+            node: node("BlizParticle", 0),
+            speed: 150.0,
+            variation: 0.1,
+            latitude: 20.0,
+            gravity: 0.0,
+            life_span: 1.0,
+            emission_rate: 30.0,
+            width: 10.0,
+            length: 10.0,
+            filter_mode: 1,
+            rows: 4,
+            columns: 4,
+            head_or_tail: 0,
+            tail_length: 1.0,
+            time: 0.5,
+            segment_colors: [color(1.0), color(0.5), color(0.0)],
+            segment_alphas: [255, 128, 0],
+            segment_scaling: [1.0, 2.0, 3.0],
+            head_intervals: [0, 15, 1],
+            head_decay_intervals: [0, 0, 1],
+            tail_intervals: [0, 0, 1],
+            tail_decay_intervals: [0, 0, 1],
+            texture_id: 0,
+            squirt: 0,
+            priority_plane: 0,
+            replaceable_id: 0,
+            speed_transform: None,
+            variation_transform: None,
+            latitude_transform: None,
+            gravity_transform: None,
+            emission_rate_transform: None,
+            width_transform: None,
+            length_transform: None,
+            visibility_transform: Some(Transform {
+                global_sequence_id: None,
+                tracks: Tracks::DontInterp(vec![Track {
+                    time: 0,
+                    value: 1.0,
+                }]),
+            }),
+            unknown_tracks: None,
+        };
+
+        let model = MDLXModel {
+            particle_emitter2_chunk: Some(ParticleEmitter2Chunk {
+                data: vec![particle_emitter2],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let particle_emitter2 = &model.particle_emitter2_chunk.as_ref().unwrap().data[0];
+        assert_eq!(particle_emitter2.node.name, "BlizParticle");
+        assert_eq!(particle_emitter2.segment_alphas, [255, 128, 0]);
+        assert_eq!(particle_emitter2.head_intervals, [0, 15, 1]);
+
+        // Fixed fields follow the 96 byte node, then the KP2V block closes the emitter
+        let f = f32::to_bits;
+        let pre2 = chunk(&bytes, PRE2_TAG, 8 + 295);
+        assert_eq!(pre2[..16], words(&[PRE2_TAG, 295, 295, 96])[..]);
+        #[rustfmt::skip]
+        let fixed = [
+            words(&[
+                f(150.0), f(0.1), f(20.0), f(0.0), f(1.0), f(30.0), f(10.0), f(10.0),
+                1, 4, 4, 0, f(1.0), f(0.5),
+                f(1.0), f(1.0), f(1.0), f(0.5), f(0.5), f(0.5), f(0.0), f(0.0), f(0.0),
+            ]),
+            vec![255, 128, 0],
+            words(&[
+                f(1.0), f(2.0), f(3.0),
+                0, 15, 1, 0, 0, 1, 0, 0, 1, 0, 0, 1,
+                0, 0, 0, 0,
+            ]),
+        ]
+        .concat();
+        assert_eq!(pre2[108..108 + 171], fixed[..]);
+        assert_eq!(
+            pre2[279..],
+            words(&[KP2V_TAG, 1, 0, 0xFFFF_FFFF, 0, f(1.0)])[..]
+        );
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn event_object_chunk_round_trip() {
+        init();
+
+        let model = MDLXModel {
+            event_object_chunk: Some(EventObjectChunk {
+                data: vec![
+                    EventObject {
+                        node: node("SNDxHDTH", 0),
+                        global_sequence_id: None,
+                        keys: Some(vec![100, 2500]),
+                    },
+                    EventObject {
+                        node: node("FPTxLEFT", 1),
+                        global_sequence_id: Some(GlobalSequenceId(0)),
+                        keys: Some(vec![]),
+                    },
+                    // No KEVT at all, last in the chunk
+                    EventObject {
+                        node: node("SPNxBLOD", 2),
+                        global_sequence_id: None,
+                        keys: None,
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let event_objects = &model.event_object_chunk.as_ref().unwrap().data;
+        assert_eq!(event_objects.len(), 3);
+        assert_eq!(event_objects[0].kind(), Some(EventKind::Sound));
+        assert_eq!(event_objects[0].event_id(), Some("HDTH"));
+        assert_eq!(event_objects[0].keys, Some(vec![100, 2500]));
+        assert_eq!(event_objects[1].kind(), Some(EventKind::Footprint));
+        assert_eq!(
+            event_objects[1].global_sequence_id,
+            Some(GlobalSequenceId(0))
+        );
+        assert_eq!(event_objects[2].keys, None);
+
+        // Objects have no size of their own, just the node and the optional KEVT block
+        let expected = [
+            words(&[EVTS_TAG, 320]),
+            node_bytes("SNDxHDTH", 0),
+            words(&[KEVT_TAG, 2, 0xFFFF_FFFF, 100, 2500]),
+            node_bytes("FPTxLEFT", 1),
+            words(&[KEVT_TAG, 0, 0]),
+            node_bytes("SPNxBLOD", 2),
+        ]
+        .concat();
+        assert_eq!(chunk(&bytes, EVTS_TAG, expected.len()), &expected[..]);
+        let read = MDLXModel::read_mdx_file([&b"MDLX"[..], &expected].concat()).unwrap();
+        assert_eq!(read.event_object_chunk, model.event_object_chunk);
+
+        // Chunk slices end right after the last object
+        let index = MdxIndex::new(&bytes).unwrap();
+        assert_eq!(&index.event_objects().unwrap().unwrap().data, event_objects);
+        let streamed = MDLXModel::read_from(&bytes[..]).unwrap();
+        assert_eq!(streamed.event_object_chunk, model.event_object_chunk);
+
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        let reparsed = reparse_mdl(&text, &model);
+        let keys = |model: &MDLXModel| {
+            let event_objects = &model.event_object_chunk.as_ref().unwrap().data;
+            event_objects
+                .iter()
+                .map(|event_object| (event_object.global_sequence_id, event_object.keys.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&reparsed), keys(&model));
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn collision_shape_chunk_round_trip() {
+        init();
+
+        let vec3 = |v: f32| Vec3 { x: v, y: v, z: v };
+        let model = MDLXModel {
+            collision_shape_chunk: Some(CollisionShapeChunk {
+                data: vec![
+                    CollisionShape {
+                        node: node("Collision Box01", 0),
+                        shape: Shape::Box {
+                            minimum: vec3(-10.0),
+                            maximum: vec3(10.0),
+                        },
+                    },
+                    CollisionShape {
+                        node: node("Collision Sphere02", 1),
+                        shape: Shape::Sphere {
+                            center: vec3(0.0),
+                            radius: 42.0,
+                        },
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let collision_shapes = &model.collision_shape_chunk.as_ref().unwrap().data;
+        assert_eq!(collision_shapes.len(), 2);
+        assert_eq!(
+            collision_shapes[1].shape,
+            Shape::Sphere {
+                center: vec3(0.0),
+                radius: 42.0,
+            }
+        );
+
+        // Node, shape type, then only the vectors and radius the shape has
+        let f = f32::to_bits;
+        #[rustfmt::skip]
+        let expected = [
+            words(&[CLID_TAG, 240]),
+            node_bytes("Collision Box01", 0),
+            words(&[0, f(-10.0), f(-10.0), f(-10.0), f(10.0), f(10.0), f(10.0)]),
+            node_bytes("Collision Sphere02", 1),
+            words(&[2, 0, 0, 0, f(42.0)]),
+        ]
+        .concat();
+        assert_eq!(chunk(&bytes, CLID_TAG, expected.len()), &expected[..]);
+        let read = MDLXModel::read_mdx_file([&b"MDLX"[..], &expected].concat()).unwrap();
+        assert_eq!(read.collision_shape_chunk, model.collision_shape_chunk);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn popcorn_emitter_chunk_round_trip() {
+        init();
+
+        let popcorn_emitter = PopcornEmitter {
+            node: node("Popcorn01", 0),
+            life_span: 1.0,
+            emission_rate: 10.0,
+            speed: 0.0,
+            color: Color {
+                b: 1.0,
+                g: 1.0,
+                r: 1.0,
+            },
+            alpha: 1.0,
+            replaceable_id: 0,
+            path: "Objects\\Spawnmodels\\Fire.pkb".to_string(),
+            animation_visibility_guide: "Birth=on".to_string(),
+            alpha_transform: None,
+            color_transform: None,
+            emission_rate_transform: None,
+            life_span_transform: None,
+            speed_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+
+        let model = MDLXModel {
+            popcorn_emitter_chunk: Some(PopcornEmitterChunk {
+                data: vec![popcorn_emitter],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let popcorn_emitter = &model.popcorn_emitter_chunk.as_ref().unwrap().data[0];
+        assert_eq!(popcorn_emitter.path, "Objects\\Spawnmodels\\Fire.pkb");
+        assert_eq!(popcorn_emitter.animation_visibility_guide, "Birth=on");
+
+        // Both strings take their whole 260 bytes, zero padded
+        let padded = |text: &str| {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize(260, 0);
+            bytes
+        };
+        let f = f32::to_bits;
+        let expected = [
+            words(&[CORN_TAG, 652, 652]),
+            node_bytes("Popcorn01", 0),
+            words(&[f(1.0), f(10.0), f(0.0), f(1.0), f(1.0), f(1.0), f(1.0), 0]),
+            padded("Objects\\Spawnmodels\\Fire.pkb"),
+            padded("Birth=on"),
+        ]
+        .concat();
+        assert_eq!(chunk(&bytes, CORN_TAG, expected.len()), &expected[..]);
+        let read = MDLXModel::read_mdx_file([&b"MDLX"[..], &expected].concat()).unwrap();
+        assert_eq!(read.popcorn_emitter_chunk, model.popcorn_emitter_chunk);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    const MDL_MODEL: &str = r#"// Hand written model
+Version {
+	FormatVersion 800,
+}
+Model "Box" {
+	BlendTime 150,
+	MinimumExtent { -1, -1, 0 },
+	MaximumExtent { 1, 1, 2 },
+}
+Sequences 1 {
+	Anim "Stand" {
+		Interval { 0, 1000 },
+		MinimumExtent { -1, -1, 0 },
+		MaximumExtent { 1, 1, 2 },
+	}
+}
+Textures 1 {
+	Bitmap {
+		Image "Textures\\Box.blp",
+		WrapWidth,
+	}
+}
+Materials 1 {
+	Material {
+		Layer {
+			FilterMode Blend,
+			TwoSided,
+			static TextureID 0,
+			Alpha 2 {
+				Hermite,
+				0: 0.0,
+					InTan 0.0,
+					OutTan 0.0,
+				1000: 1.0,
+					InTan 1.0,
+					OutTan 1.0,
+			}
+		}
+	}
+}
+Geoset {
+	Vertices 3 {
+		{ 0, 0, 0 },
+		{ 1, 0, 0 },
+		{ 0, 1, 0 },
+	}
+	Normals 3 {
+		{ 0, 0, 1 },
+		{ 0, 0, 1 },
+		{ 0, 0, 1 },
+	}
+	TVertices 3 {
+		{ 0, 0 },
+		{ 1, 0 },
+		{ 0, 1 },
+	}
+	VertexGroup {
+		0,
+		0,
+		0,
+	}
+	Faces 1 3 {
+		Triangles {
+			{ 0, 1, 2 },
+		}
+	}
+	Groups 1 1 {
+		Matrices { 0 },
+	}
+	MinimumExtent { 0, 0, 0 },
+	MaximumExtent { 1, 1, 0 },
+	BoundsRadius 1.0,
+	Anim {
+		BoundsRadius 1.0,
+	}
+	MaterialID 0,
+	SelectionGroup 0,
+}
+Bone "Root" {
+	ObjectId 0,
+	GeosetId 0,
+	GeosetAnimId None,
+	Rotation 1 {
+		Linear,
+		GlobalSeqId 0,
+		500: { 0, 0, 0.707107, 0.707107 },
+	}
+}
+EventObject "SNDxBOX1" {
+	ObjectId 1,
+	Parent 0,
+	EventTrack 2 {
+		100,
+		600,
+	}
+}
+PivotPoints 2 {
+	{ 0, 0, 0 },
+	{ 0, 0, 1 },
+}
+GlobalSequences 1 {
+	Duration 1000,
+}
+"#;
+
+    #[test]
+    fn read_mdl_file() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        assert_eq!(model.model_chunk.as_ref().unwrap().blend_time, 150);
+        assert_eq!(model.texture_chunk.as_ref().unwrap().data[0].flags, 0x1);
+
+        let layer = &model.material_chunk.as_ref().unwrap().data[0].layers[0];
+        assert_eq!(layer.filter_mode, 2);
+        assert_eq!(layer.shading_flags, 0x10);
+        let alpha = layer.alpha_transform.as_ref().unwrap();
+        assert_eq!(alpha.interpolation(), Interpolation::Hermite);
+        assert_eq!(alpha.tracks.len(), 2);
+
+        let geoset = &model.geoset_chunk.as_ref().unwrap().data[0];
+        assert_eq!(geoset.vertex_positions.len(), 3);
+        assert_eq!(geoset.faces.len(), 1);
+
+        let bone = &model.bone_chunk.as_ref().unwrap().data[0];
+        assert_eq!(bone.node.flags, 0x100);
+        assert_eq!(bone.geoset_animation_id, 0xFFFF_FFFF);
+        let rotation = bone.node.rotation.as_ref().unwrap();
+        assert_eq!(rotation.global_sequence_id, Some(GlobalSequenceId(0)));
+
+        let event_object = &model.event_object_chunk.as_ref().unwrap().data[0];
+        assert_eq!(event_object.kind(), Some(EventKind::Sound));
+        assert_eq!(event_object.keys, Some(vec![100, 600]));
+
+        // Sizes and counts must match what the mdx reader sees
+        let (_, mut resaved) = resave(MDLXModel::read_mdl_file(MDL_MODEL).unwrap());
+        resaved.chunk_order.clear();
+        assert_eq!(model, resaved);
+    }
+
+    #[test]
+    fn read_mdl_file_error_position() {
+        let error =
+            MDLXModel::read_mdl_file("Model \"Box\" {\n\tBlendTime 150,\n\tFoo 1,\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 2));
+
+        let error = MDLXModel::read_mdl_file("Sequences 1 {\n\tAnim \"Stand\" {\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 15));
+    }
+
+    #[test]
+    fn tangents_follow_interpolation() {
+        init();
+
+        let bone = |interpolation: &str, tangents: &str| {
+            format!(
+                "Bone \"Root\" {{\n\tRotation 1 {{\n\t\t{},\n\t\t0: {{ 0, 0, 0, 1 }},\n{}\t}}\n}}",
+                interpolation, tangents
+            )
+        };
+        let tangents = "\t\t\tInTan { 0, 0, 0, 1 },\n\t\t\tOutTan { 0, 0, 0, 1 },\n";
+        let model = MDLXModel::read_mdl_file(&bone("Bezier", tangents)).unwrap();
+        let rotation = model.bone_chunk.as_ref().unwrap().data[0]
+            .node
+            .rotation
+            .as_ref()
+            .unwrap();
+        assert_eq!(rotation.interpolation(), Interpolation::Bezier);
+        assert_eq!(rotation.global_sequence_id, None);
+
+        let error = MDLXModel::read_mdl_file(&bone("Hermite", "")).unwrap_err();
+        assert_eq!(error.message, "Key 0 is missing tangents");
+        let error = MDLXModel::read_mdl_file(&bone("Linear", tangents)).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Key 0 has tangents without hermite or bezier interpolation"
+        );
+
+        // Interpolation types past bezier can't be read
+        let mut bytes = MDLXModel::write_mdx_file(model).unwrap();
+        let rotation = bytes
+            .windows(4)
+            .position(|window| window == KGRT_TAG.to_le_bytes())
+            .unwrap();
+        bytes[rotation + 8..rotation + 12].copy_from_slice(&7u32.to_le_bytes());
+        let error = MDLXModel::read_mdx_file(bytes).unwrap_err();
+        assert_eq!(
+            error.kind,
+            MdxErrorKind::InvalidValue("unknown interpolation type 7".to_string())
+        );
+    }
+
+    #[test]
+    fn write_mdl_file() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        assert!(text.contains("\t\t\tAlpha 2 {\n\t\t\t\tHermite,\n"));
+        // Animated properties have no static value next to them
+        assert!(!text.contains("static Alpha"));
+        assert!(text.contains("static TextureID 0,"));
+        assert!(text.contains("\t\tLinear,\n\t\tGlobalSeqId 0,\n"));
+        assert_eq!(MDLXModel::read_mdl_file(&text).unwrap(), model);
+
+        // mdx -> mdl -> mdx must give the same bytes
+        let (bytes, model) = resave(model);
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        let (resaved_bytes, _) = resave(MDLXModel::read_mdl_file(&text).unwrap());
+        assert_eq!(bytes, resaved_bytes);
+
+        // Quotes and backslashes are escaped, single backslashes still read as they are
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        assert_eq!(
+            model.texture_chunk.as_ref().unwrap().data[0].file_name,
+            "Textures\\Box.blp"
+        );
+        let single = MDLXModel::read_mdl_file(&MDL_MODEL.replace("\\\\", "\\")).unwrap();
+        assert_eq!(single, model);
+        model.bone_chunk.as_mut().unwrap().data[0].node.name = "Root \"Main\"".to_string();
+        model.texture_chunk.as_mut().unwrap().data[0].file_name = "Textures\\".to_string();
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        assert!(text.contains("Bone \"Root \\\"Main\\\"\" {"));
+        assert!(text.contains("Image \"Textures\\\\\","));
+        assert_eq!(MDLXModel::read_mdl_file(&text).unwrap(), model);
+    }
+
+    #[test]
+    fn write_mdl_rejects_what_mdl_cannot_hold() {
+        init();
+
+        let written = |model: &MDLXModel| MDLXModel::write_mdl_file(model).map_err(|e| e.message);
+
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.material_chunk.as_mut().unwrap().data[0].layers[0].filter_mode = 42;
+        assert_eq!(
+            written(&model),
+            Err("Unknown filter mode 42 can't be written to MDL".to_string())
+        );
+
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.bone_chunk.as_mut().unwrap().data[0]
+            .node
+            .unknown_tracks = Some(UnknownTracks {
+            tag: 0x5846_524B,
+            bytes: Vec::new(),
+        });
+        assert_eq!(
+            written(&model),
+            Err("Unknown track KRFX can't be written to MDL".to_string())
+        );
+
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.pivot_point_chunk.as_mut().unwrap().data[0].position[2] = f32::NAN;
+        assert_eq!(
+            written(&model),
+            Err("NaN and infinite values can't be written to MDL".to_string())
+        );
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.sequence_chunk.as_mut().unwrap().data[0].move_speed = f32::INFINITY;
+        let error = MDLXModel::write_mdl_file(&model).unwrap_err();
+        model.sequence_chunk.as_mut().unwrap().data[0].move_speed = 2.0;
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        assert_eq!(text.lines().nth(error.line - 1), Some("\t\tMoveSpeed 2,"));
+
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.unknown_chunks.push(UnknownChunk {
+            tag: 0x4443_4241,
+            bytes: b"abc".to_vec(),
+        });
+        assert_eq!(
+            written(&model),
+            Err("Unknown chunk ABCD can't be written to MDL".to_string())
+        );
+
+        // The color flag survives with or without an animated color
+        let geoset_animation = |flags, color, geoset_color| GeosetAnimation {
+            alpha: 1.0,
+            flags,
+            color,
+            geoset_id: 0,
+            geoset_alpha: None,
+            geoset_color,
+            unknown_tracks: None,
+        };
+        let color_track = || Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Linear(vec![Track {
+                time: 0,
+                value: Color {
+                    b: 1.0,
+                    g: 0.5,
+                    r: 0.0,
+                },
+            }]),
+        };
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.geoset_animation_chunk = Some(GeosetAnimationChunk {
+            data: vec![
+                geoset_animation(0x1, [1.0, 1.0, 1.0], None),
+                geoset_animation(0x3, [0.5, 0.25, 1.0], None),
+                geoset_animation(0x2, [1.0, 1.0, 1.0], Some(color_track())),
+            ],
+        });
+        let text = written(&model).unwrap();
+        assert_eq!(text.matches("static Color").count(), 1);
+        assert_eq!(MDLXModel::read_mdl_file(&text).unwrap(), model);
+
+        model.geoset_animation_chunk.as_mut().unwrap().data[2].flags = 0;
+        assert_eq!(
+            written(&model),
+            Err("Animated geoset color without the color flag can't be written to MDL".to_string())
+        );
+        model.geoset_animation_chunk.as_mut().unwrap().data[2].flags = 0x6;
+        assert_eq!(
+            written(&model),
+            Err("Geoset animation flags 0x6 can't be written to MDL".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_chunk_round_trip() {
+        init();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"MDLXVERS\x04\0\0\0");
+        bytes.extend_from_slice(&800u32.to_le_bytes());
+        bytes.extend_from_slice(b"ABCD\x03\0\0\0abc");
+        bytes.extend_from_slice(b"XTRA\0\0\0\0");
+
+        let model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+        assert_eq!(model.version_chunk.as_ref().unwrap().version, 800);
+        assert_eq!(
+            model.unknown_chunks,
+            vec![
+                UnknownChunk {
+                    tag: 0x4443_4241,
+                    bytes: b"abc".to_vec(),
+                },
+                UnknownChunk {
+                    tag: 0x4152_5458,
+                    bytes: Vec::new(),
+                },
+            ]
+        );
+
+        let (resaved, _) = resave(model);
+        assert_eq!(resaved, bytes);
+
+        // Unknown chunks added later go last
+        let mut model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+        model.unknown_chunks.push(UnknownChunk {
+            tag: 0x4E57_454E,
+            bytes: b"new".to_vec(),
+        });
+        let (resaved, _) = resave(model);
+        assert!(resaved.ends_with(b"XTRA\0\0\0\0NEWN\x03\0\0\0new"));
+
+        // Unknown chunks can't take the tag of a typed chunk, or both would be written
+        for tag in [VERS_TAG, GEOS_TAG] {
+            let mut model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+            model.unknown_chunks.push(UnknownChunk {
+                tag,
+                bytes: 900u32.to_le_bytes().to_vec(),
+            });
+            assert_eq!(
+                MDLXModel::write_mdx_file(model).unwrap_err().to_string(),
+                format!(
+                    "unknown chunk has the tag of the typed chunk {}",
+                    FourCC(tag)
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_tracks_round_trip() {
+        init();
+
+        let mut node = node("Attach", 0);
+        node.unknown_tracks = Some(UnknownTracks {
+            tag: 0x5858_474B,
+            bytes: vec![1, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF],
+        });
+
+        let attachment = Attachment {
+            node,
+            path: String::new(),
+            attachment_id: 0,
+            visibility: None,
+            unknown_tracks: Some(UnknownTracks {
+                tag: 0x5858_544B,
+                bytes: vec![0xAB; 6],
+            }),
+        };
+
+        let model = MDLXModel {
+            attachment_chunk: Some(AttachmentChunk {
+                data: vec![attachment],
+            }),
+            ..Default::default()
+        };
+
+        let (bytes, model) = resave(model);
+        let attachment = &model.attachment_chunk.as_ref().unwrap().data[0];
+        let node_tracks = attachment.node.unknown_tracks.as_ref().unwrap();
+        assert_eq!(node_tracks.tag, 0x5858_474B);
+        assert_eq!(node_tracks.bytes.len(), 12);
+        let attachment_tracks = attachment.unknown_tracks.as_ref().unwrap();
+        assert_eq!(attachment_tracks.bytes, vec![0xAB; 6]);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn geoset_v900_round_trip() {
+        init();
+
+        let vertex_data = r#"	Tangents 3 {
+		{ 1, 0, 0, 1 },
+		{ 1, 0, 0, 1 },
+		{ 1, 0, 0, -1 },
+	}
+	SkinWeights 3 {
+		0, 0, 0, 0, 255, 0, 0, 0,
+		0, 0, 0, 0, 255, 0, 0, 0,
+		0, 1, 0, 0, 128, 127, 0, 0,
+	}
+	VertexGroup {"#;
+        let lod = "\tMaterialID 0,\n\tLevelOfDetail 1,\n\tName \"Box_LOD1\",";
+        let text = MDL_MODEL
+            .replace("FormatVersion 800", "FormatVersion 900")
+            .replace("\tVertexGroup {", vertex_data)
+            .replace("\tMaterialID 0,", lod);
+        let model = MDLXModel::read_mdl_file(&text).unwrap();
+
+        let (bytes, model) = resave(model);
+        let geoset = &model.geoset_chunk.as_ref().unwrap().data[0];
+        let lod = geoset.lod.as_ref().unwrap();
+        assert_eq!(lod.lod, 1);
+        assert_eq!(lod.lod_name, "Box_LOD1");
+        let tangents = geoset.tangents.as_ref().unwrap();
+        assert_eq!(tangents.len(), 3);
+        assert_eq!(tangents[2].w, -1.0);
+        let skin = geoset.skin.as_ref().unwrap();
+        assert_eq!(skin.len(), 24);
+        assert_eq!(&skin[16..24], &[0, 1, 0, 0, 128, 127, 0, 0]);
+
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        assert!(written.contains("LevelOfDetail 1,"));
+        assert_eq!(reparse_mdl(&written, &model), model);
+
+        let (resaved_bytes, mut model) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // Going back to 800 fails while tangents or skin weights are left, the LOD just goes
+        model.version_chunk.as_mut().unwrap().version = 800;
+        let error = MDLXModel::write_mdx_file(model).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "geoset 0 has tangents or skin weights, which need version 900 or later"
+        );
+        let error =
+            MDLXModel::read_mdl_file(&text.replace("FormatVersion 900", "FormatVersion 800"))
+                .unwrap_err();
+        assert_eq!(error.line, 2);
+        let mut model = MDLXModel::read_mdl_file(&text).unwrap();
+        model.version_chunk.as_mut().unwrap().version = 800;
+        let geoset = &mut model.geoset_chunk.as_mut().unwrap().data[0];
+        geoset.tangents = None;
+        geoset.skin = None;
+        let (_, model) = resave(model);
+        let geoset = &model.geoset_chunk.as_ref().unwrap().data[0];
+        assert_eq!(geoset.lod, None);
+        assert_eq!(geoset.tangents, None);
+        assert_eq!(geoset.skin, None);
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (_, model) = resave(model);
+        let geoset = &model.geoset_chunk.as_ref().unwrap().data[0];
+        assert_eq!(geoset.lod, None);
+        assert_eq!(geoset.tangents, None);
+    }
+
+    #[test]
+    fn material_hd_round_trip() {
+        init();
+
+        let layer = r#"static TextureID 0,
+			static EmissiveGain 2,
+			static FresnelColor { 0.5, 0.25, 1 },
+			static FresnelOpacity 0.75,
+			FresnelTeamColor 1 {
+				DontInterp,
+				0: 1,
+			}"#;
+        let text = MDL_MODEL
+            .replace("FormatVersion 800", "FormatVersion 1000")
+            .replace(
+                "\t\tLayer {",
+                "\t\tShader \"Shader_HD_DefaultUnit\",\n\t\tLayer {",
+            )
+            .replace("static TextureID 0,", layer);
+        let model = MDLXModel::read_mdl_file(&text).unwrap();
+
+        let (bytes, model) = resave(model);
+        let material = &model.material_chunk.as_ref().unwrap().data[0];
+        assert_eq!(material.shader.as_deref(), Some("Shader_HD_DefaultUnit"));
+        let hd = material.layers[0].hd.as_ref().unwrap();
+        assert_eq!(hd.emissive_gain, 2.0);
+        assert_eq!(hd.fresnel_color.y, 0.25);
+        assert_eq!(hd.fresnel_opacity, 0.75);
+        assert!(material.layers[0].fresnel_team_color_transform.is_some());
+
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        assert!(written.contains("Shader \"Shader_HD_DefaultUnit\","));
+        assert_eq!(reparse_mdl(&written, &model), model);
+
+        let (resaved_bytes, mut model) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // Layers of version 1100 keep what follows the HD fields as they are
+        let mut unknown_bytes = words(&[1, 0, 0]);
+        unknown_bytes.extend_from_slice(b"KMTA");
+        unknown_bytes.extend(words(&[1, 0, 0xFFFF_FFFF, 0, 1.0f32.to_bits()]));
+        let layer = &mut model.material_chunk.as_mut().unwrap().data[0].layers[0];
+        layer.alpha_transform = None;
+        layer.fresnel_team_color_transform = None;
+        layer.unknown_bytes = Some(unknown_bytes.clone());
+        model.version_chunk.as_mut().unwrap().version = 1100;
+
+        let (bytes, mut model) = resave(model);
+        let start = bytes.windows(4).position(|tag| tag == b"LAYS").unwrap() + 8;
+        let inclusive_size = 4 * 7 + 24 + unknown_bytes.len();
+        assert_eq!(
+            bytes[start..start + 4],
+            (inclusive_size as u32).to_le_bytes()
+        );
+        assert_eq!(bytes[start + 52..start + inclusive_size], unknown_bytes[..]);
+        let layer = &model.material_chunk.as_ref().unwrap().data[0].layers[0];
+        assert_eq!(layer.unknown_bytes, Some(unknown_bytes));
+        assert_eq!(layer.alpha_transform, None);
+        assert_eq!(
+            MDLXModel::write_mdl_file(&model).unwrap_err().message,
+            "Layers of version 1100 can't be written to MDL"
+        );
+        model.version_chunk.as_mut().unwrap().version = 1000;
+        assert!(MDLXModel::write_mdx_file(model).is_err());
+
+        // Writer follows the version, filling in HD defaults or dropping them
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.version_chunk.as_mut().unwrap().version = 900;
+        let (_, mut model) = resave(model);
+        let material = &model.material_chunk.as_ref().unwrap().data[0];
+        assert_eq!(material.shader.as_deref(), Some(""));
+        assert_eq!(material.layers[0].hd, Some(LayerHd::default()));
+        assert_eq!(material.layers[0].unknown_bytes, None);
+        assert!(model.geoset_chunk.as_ref().unwrap().data[0].lod.is_some());
+
+        model.version_chunk.as_mut().unwrap().version = 800;
+        let (bytes, _) = resave(model);
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        assert_eq!(bytes, MDLXModel::write_mdx_file(model).unwrap());
+    }
+
+    #[test]
+    fn face_effect_chunk_round_trip() {
+        init();
+
+        let face_effect = r#"FaceFX "Arthas" {
+	Path "Units\Human\Arthas\Arthas.facefx",
+}
+FaceFX "Arthas_Alt" {
+	Path "",
+}
+"#;
+        let model = MDLXModel::read_mdl_file(&(MDL_MODEL.to_string() + face_effect)).unwrap();
+
+        let (bytes, model) = resave(model);
+        let face_effect_chunk = model.face_effect_chunk.as_ref().unwrap();
+        assert_eq!(face_effect_chunk.chunk_size(), 2 * 340);
+        assert_eq!(face_effect_chunk.data[0].target, "Arthas");
+        assert_eq!(
+            face_effect_chunk.data[0].path,
+            "Units\\Human\\Arthas\\Arthas.facefx"
+        );
+        assert_eq!(face_effect_chunk.data[1].target, "Arthas_Alt");
+
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        assert_eq!(reparse_mdl(&written, &model), model);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+    }
+
+    #[test]
+    fn bind_pose_round_trip() {
+        init();
+
+        let bind_pose = r#"BindPose {
+	Matrices 2 {
+		{ 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0 },
+		{ 1, 0, 0, 0, 1, 0, 0, 0, 1, 0.5, -1, 2 },
+	}
+}
+"#;
+        let text = MDL_MODEL.replace("FormatVersion 800", "FormatVersion 1000") + bind_pose;
+        let model = MDLXModel::read_mdl_file(&text).unwrap();
+
+        let (bytes, model) = resave(model);
+        assert_eq!(model.bind_matrix(1).unwrap().matrix[9..], [0.5, -1.0, 2.0]);
+        assert_eq!(model.bind_matrix(2), None);
+
+        let written = MDLXModel::write_mdl_file(&model).unwrap();
+        assert!(written.contains("Matrices 2 {"));
+        let reparsed = MDLXModel::read_mdl_file(&written).unwrap();
+        assert_eq!(reparsed.bind_pose_chunk, model.bind_pose_chunk);
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // Texture sets have no known layout, they pass through in place as they are
+        let mut bytes = MDLXModel::write_mdx_file(reparsed).unwrap();
+        let bind_pose = bytes.windows(4).position(|tag| tag == b"BPOS").unwrap();
+        bytes.splice(bind_pose..bind_pose, b"TXST\x03\0\0\0abc".iter().cloned());
+        let model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+        assert_eq!(
+            model.unknown_chunks,
+            vec![UnknownChunk {
+                tag: TXST_TAG,
+                bytes: b"abc".to_vec(),
+            }]
+        );
+        assert_eq!(MDLXModel::write_mdx_file(model).unwrap(), bytes);
+    }
+
+    #[test]
+    fn chunk_order_round_trip() {
+        init();
+
+        // Textures after materials and sequences after geosets
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        model.chunk_order = vec![VERS_TAG, MODL_TAG, MTLS_TAG, TEXS_TAG, GEOS_TAG, SEQS_TAG];
+        model.unknown_chunks.push(UnknownChunk {
+            tag: 0x4443_4241,
+            bytes: b"abc".to_vec(),
+        });
+        model.chunk_order.insert(3, 0x4443_4241);
+
+        let (bytes, model) = resave(model);
+        assert_eq!(
+            model.chunk_order,
+            vec![
+                VERS_TAG,
+                MODL_TAG,
+                MTLS_TAG,
+                0x4443_4241,
+                TEXS_TAG,
+                GEOS_TAG,
+                SEQS_TAG,
+                GLBS_TAG,
+                BONE_TAG,
+                PIVT_TAG,
+                EVTS_TAG,
+            ]
+        );
+
+        let (resaved_bytes, _) = resave(model);
+        assert_eq!(bytes, resaved_bytes);
+
+        // New models use the canonical order
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (_, model) = resave(model);
+        assert_eq!(
+            model.chunk_order,
+            vec![
+                VERS_TAG, MODL_TAG, SEQS_TAG, GLBS_TAG, TEXS_TAG, MTLS_TAG, GEOS_TAG, BONE_TAG,
+                PIVT_TAG, EVTS_TAG,
+            ]
+        );
+    }
+
+    // Hands out one byte per read call
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn read_from_stream() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (bytes, model) = resave(model);
+
+        assert_eq!(MDLXModel::read_from(&bytes[..]).unwrap(), model);
+        assert_eq!(MDLXModel::read_from(Trickle(&bytes)).unwrap(), model);
+        let cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(MDLXModel::read_from_seekable(cursor).unwrap(), model);
+
+        let error = MDLXModel::read_from(&b"MDLXVERS"[..]).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(VERS_TAG)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, 8);
+
+        let error = MDLXModel::read_from(&b"MDLYVERS"[..]).unwrap_err();
+        assert_eq!(error.kind, MdxErrorKind::NotMdx);
+
+        // Size claims more than the stream holds
+        let truncated = &bytes[..bytes.len() - 4];
+        let error = MDLXModel::read_from(truncated).unwrap_err();
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, truncated.len());
+
+        let cursor = std::io::Cursor::new(b"MDLXABCD\xff\xff\xff\x7fabcd");
+        let error = MDLXModel::read_from_seekable(cursor).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(0x4443_4241)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, 12);
+
+        // IO errors coming through scroll stay IO errors
+        let io_error = std::io::Error::other("disk gone");
+        let error = MdxError::from(scroll::Error::IO(io_error));
+        assert_eq!(error.kind, MdxErrorKind::Io("disk gone".to_string()));
+    }
+
+    #[test]
+    fn write_to_stream() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (bytes, model) = resave(model);
+
+        let mut written = Vec::new();
+        model.write_to(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        // Full writers fail instead of truncating
+        let model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+        let mut buffer = vec![0u8; bytes.len() - 1];
+        let error = model.write_to(&mut buffer[..]).unwrap_err();
+        assert!(matches!(error, scroll::Error::IO(_)));
+    }
+
+    #[test]
+    fn index_decodes_single_chunks() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (bytes, model) = resave(model);
+        let index = MdxIndex::new(&bytes).unwrap();
+
+        let tags: Vec<u32> = index.entries().iter().map(|entry| entry.tag).collect();
+        assert_eq!(tags, model.chunk_order);
+        let sequences = index.entry(SEQS_TAG).unwrap();
+        assert_eq!(
+            &bytes[sequences.offset..sequences.offset + 4],
+            &SEQS_TAG.to_le_bytes()
+        );
+
+        assert_eq!(index.version().unwrap(), 800);
+        assert_eq!(index.sequences().unwrap(), model.sequence_chunk);
+        assert_eq!(index.textures().unwrap(), model.texture_chunk);
+        assert_eq!(index.geosets().unwrap(), model.geoset_chunk);
+        assert_eq!(index.materials().unwrap(), model.material_chunk);
+        assert_eq!(index.bones().unwrap(), model.bone_chunk);
+        assert_eq!(index.cameras().unwrap(), None);
+
+        // Only the chunk asked for is decoded
+        let mut broken = bytes.clone();
+        let geosets = index.entry(GEOS_TAG).unwrap();
+        broken[geosets.offset + 8..geosets.offset + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+        let index = MdxIndex::new(&broken).unwrap();
+        assert_eq!(index.sequences().unwrap(), model.sequence_chunk);
+        let error = index.geosets().unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(GEOS_TAG)));
+
+        let error = MdxIndex::new(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, bytes.len() - 1);
+    }
+
+    #[test]
+    fn read_mdx_file_hostile_sizes() {
+        init();
+
+        let model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let (bytes, _) = resave(model);
+        let geosets = MdxIndex::new(&bytes)
+            .unwrap()
+            .entry(GEOS_TAG)
+            .unwrap()
+            .offset;
+
+        // Helper that claims no size
+        let mut helpers = b"MDLXHELP\x60\0\0\0".to_vec();
+        helpers.extend_from_slice(&[0u8; 96]);
+        let error = MDLXModel::read_mdx_file(helpers).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(HELP_TAG)));
+        assert_eq!(error.path, vec!["helper 0"]);
+        assert_eq!(error.offset, 12);
+        assert_eq!(
+            error.kind,
+            MdxErrorKind::SizeMismatch {
+                expected: 0,
+                found: 96
+            }
+        );
+
+        // Vertex count far past the end of the file
+        let mut broken = bytes.clone();
+        broken[geosets + 16..geosets + 20].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = MDLXModel::read_mdx_file(broken).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(GEOS_TAG)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn sizes_and_counts_follow_edits() {
+        init();
+
+        let mut model = MDLXModel::read_mdl_file(MDL_MODEL).unwrap();
+        let geoset = &mut model.geoset_chunk.as_mut().unwrap().data[0];
+        geoset.vertex_positions.push(VertexPosition {
+            position: [0.0, 0.0, 1.0],
+        });
+        geoset.vertex_normals.push(VertexNormal {
+            normal: [0.0, 0.0, 1.0],
+        });
+        geoset.vertex_groups.push(VertexGroup { matrix_group: 0 });
+        geoset.texture_coordinate_sets[0]
+            .texture_coordinates
+            .push([0.5, 0.5]);
+        geoset.faces.push(Face {
+            index1: 0,
+            index2: 2,
+            index3: 3,
+        });
+        model.sequence_chunk.as_mut().unwrap().data.pop();
+
+        let (bytes, resaved) = resave(model);
+        let geoset = &resaved.geoset_chunk.as_ref().unwrap().data[0];
+        assert_eq!(geoset.vertex_positions.len(), 4);
+        assert_eq!(geoset.faces.len(), 2);
+        assert_eq!(
+            geoset.texture_coordinate_sets[0].texture_coordinates.len(),
+            4
+        );
+        let sequences = resaved.sequence_chunk.as_ref().unwrap();
+        assert_eq!(sequences.chunk_size(), sequences.data.len() as u32 * 132);
+
+        // Headers that disagree with the data are errors
+        let index = MdxIndex::new(&bytes).unwrap();
+        let sequences = index.entry(SEQS_TAG).unwrap().offset;
+        let mut broken = bytes.clone();
+        broken[sequences + 4..sequences + 8].copy_from_slice(&133u32.to_le_bytes());
+        broken.splice(sequences + 8..sequences + 8, vec![0u8; 133 - 132]);
+        let error = MDLXModel::read_mdx_file(broken).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(SEQS_TAG)));
+        assert_eq!(
+            error.kind,
+            MdxErrorKind::SizeMismatch {
+                expected: 133,
+                found: 132
+            }
+        );
+    }
+
+    #[test]
+    fn model_builder_builds_valid_models() {
+        init();
+
+        let mut builder = ModelBuilder::new("Effect");
+        let texture = builder.add_texture("Textures\\Spark.blp");
+        let material = builder.add_material(texture, 3);
+        let root = builder.add_bone("Root", None);
+        let spark = builder.add_bone_at("Spark", Some(root), [0.0, 0.0, 2.0]);
+        builder.add_geoset(Mesh {
+            positions: vec![[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]],
+            uvs: vec![[0.0, 0.0], [1.0, 0.0], [0.5, 1.0]],
+            faces: vec![[0, 1, 2]],
+            material_id: material,
+            bones: vec![spark],
+            ..Mesh::default()
+        });
+        builder.add_sequence("Birth", 0..=500);
+        builder.add_sequence("Stand", 500..=1500);
+        let model = builder.build().unwrap();
+
+        assert_eq!(spark, 1);
+        let bones = &model.bone_chunk.as_ref().unwrap().data;
+        assert_eq!(bones[1].node.object_id, 1);
+        assert_eq!(bones[1].node.parent_id, 0);
+        let pivots = &model.pivot_point_chunk.as_ref().unwrap().data;
+        assert_eq!(pivots.len(), bones.len());
+        assert_eq!(pivots[1].position, [0.0, 0.0, 2.0]);
+
+        let geoset = &model.geoset_chunk.as_ref().unwrap().data[0];
+        assert_eq!(geoset.extent_sequences.len(), 2);
+        assert_eq!(geoset.extent.maximum.z, 2.0);
+        assert_eq!(geoset.vertex_normals.len(), 3);
+        let extent = &model.model_chunk.as_ref().unwrap().extent;
+        assert_eq!(extent.minimum.x, -1.0);
+        assert_eq!(extent.bounds_radius, 2f32.sqrt());
+        let sequences = &model.sequence_chunk.as_ref().unwrap().data;
+        assert_eq!(sequences[1].interval_start, 500);
+        assert_eq!(sequences[1].interval_end, 1500);
+
+        // Survives both formats unchanged
+        let text = MDLXModel::write_mdl_file(&model).unwrap();
+        assert_eq!(MDLXModel::read_mdl_file(&text).unwrap(), model);
+        let (_, resaved) = resave(model);
+        assert_eq!(MDLXModel::write_mdl_file(&resaved).unwrap(), text);
+
+        // Ids that point nowhere are caught on build
+        let mut builder = ModelBuilder::new("Broken");
+        builder.add_material(0, 0);
+        let error = builder.build().unwrap_err();
+        assert_eq!(error.path, vec!["material 0".to_string()]);
+
+        // Names have to fit their fields, terminator included
+        let mut builder = ModelBuilder::new("Broken");
+        builder.add_bone(&"b".repeat(79), None);
+        builder.add_sequence(&"s".repeat(80), 0..=100);
+        let error = builder.build().unwrap_err();
+        assert_eq!(error.path, vec!["sequence 0".to_string()]);
+
+        let mut builder = ModelBuilder::new("Broken");
+        builder.add_texture(&"t".repeat(256));
+        let error = builder.build().unwrap_err();
+        assert_eq!(error.path, vec!["texture 0".to_string()]);
+
+        let mut builder = ModelBuilder::new("Broken");
+        let texture = builder.add_texture("Textures\\Spark.blp");
+        let material = builder.add_material(texture, 0);
+        builder.add_geoset(Mesh {
+            positions: vec![[0.0; 3]],
+            faces: vec![[0, 0, 1]],
+            material_id: material,
+            ..Mesh::default()
+        });
+        let error = builder.build().unwrap_err();
+        assert_eq!(error.path, vec!["geoset 0".to_string()]);
+        assert_eq!(
+            error.kind,
+            MdxErrorKind::InvalidValue("vertex 1 doesn't exist".to_string())
+        );
+    }
+
+    #[test]
+    fn transform_sample() {
+        init();
+
+        let key = |time: u32, value: f32| Track { time, value };
+        let keys = vec![
+            key(0, 5.0),
+            key(100, 0.0),
+            key(200, 1.0),
+            key(300, 3.0),
+            key(1000, 7.0),
+        ];
+        let mut alpha = Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Linear(keys.clone()),
+        };
+        assert_eq!(alpha.sample(150, 100..=300, &[]).unwrap(), Some(0.5));
+        assert_eq!(alpha.sample(250, 100..=300, &[]).unwrap(), Some(2.0));
+        // Clamped to the interval, keys of other sequences don't count
+        assert_eq!(alpha.sample(50, 100..=300, &[]).unwrap(), Some(0.0));
+        assert_eq!(alpha.sample(900, 100..=300, &[]).unwrap(), Some(3.0));
+        assert_eq!(alpha.sample(150, 120..=300, &[]).unwrap(), Some(1.0));
+        assert_eq!(alpha.sample(290, 120..=290, &[]).unwrap(), Some(1.0));
+        assert_eq!(alpha.sample(500, 400..=900, &[]).unwrap(), None);
+        // Both ends of the interval are part of it
+        assert_eq!(alpha.sample(500, 400..=1000, &[]).unwrap(), Some(7.0));
+        assert_eq!(alpha.sample(1000, 1000..=1200, &[]).unwrap(), Some(7.0));
+
+        // Global sequences loop on their own
+        let global_sequences = [GlobalSequence { duration: 1000 }];
+        alpha.global_sequence_id = Some(GlobalSequenceId(0));
+        assert_eq!(
+            alpha.sample(2150, 100..=300, &global_sequences).unwrap(),
+            Some(0.5)
+        );
+        assert_eq!(
+            alpha.sample(1999, 100..=300, &global_sequences).unwrap(),
+            Some(7.0 - 4.0 / 700.0)
+        );
+        alpha.global_sequence_id = Some(GlobalSequenceId(1));
+        assert_eq!(
+            alpha
+                .sample(150, 100..=300, &global_sequences)
+                .unwrap_err()
+                .kind,
+            MdxErrorKind::InvalidValue("global sequence 1 doesn't exist".to_string())
+        );
+
+        let steps = Transform {
+            global_sequence_id: None,
+            tracks: Tracks::DontInterp(keys),
+        };
+        assert_eq!(steps.sample(199, 100..=300, &[]).unwrap(), Some(0.0));
+        assert_eq!(steps.sample(200, 100..=300, &[]).unwrap(), Some(1.0));
+
+        let tangent_key = |time: u32, value: f32| TangentTrack {
+            time,
+            value,
+            in_tan: 0.0,
+            out_tan: 0.0,
+        };
+        let tangent_keys = vec![tangent_key(0, 0.0), tangent_key(100, 1.0)];
+        let mut curve = Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Hermite(tangent_keys.clone()),
+        };
+        assert_eq!(curve.sample(50, 0..=100, &[]).unwrap(), Some(0.5));
+        assert_eq!(curve.sample(25, 0..=100, &[]).unwrap(), Some(0.15625));
+        curve.tracks = Tracks::Bezier(tangent_keys);
+        assert_eq!(curve.sample(50, 0..=100, &[]).unwrap(), Some(0.125));
+
+        let quat = |x: f32, w: f32| Vec4 {
+            x,
+            y: 0.0,
+            z: 0.0,
+            w,
+        };
+        let rotation = Transform {
+            global_sequence_id: None,
+            tracks: Tracks::Linear(vec![
+                Track {
+                    time: 0,
+                    value: quat(0.0, 1.0),
+                },
+                Track {
+                    time: 100,
+                    value: quat(-1.0, 0.0),
+                },
+            ]),
+        };
+        // Halfway through a half turn, taking the short way round
+        let half = rotation.sample(50, 0..=100, &[]).unwrap().unwrap();
+        assert!((half.x + 0.5f32.sqrt()).abs() < 1e-6);
+        assert!((half.w - 0.5f32.sqrt()).abs() < 1e-6);
+        let length = half.x * half.x + half.y * half.y + half.z * half.z + half.w * half.w;
+        assert!((length - 1.0).abs() < 1e-6);
+    }
+
+    // Every other chunk, each with some animated block, to go along with MDL_MODEL
+    const FUZZ_MDL: &str = r#"TextureAnims 1 {
+	TVertexAnim {
+		Translation 1 {
+			Linear,
+			0: { 0, 0.5, 0 },
+		}
+	}
+}
+GeosetAnim {
+	static Alpha 1,
+	Color 1 {
+		Hermite,
+		0: { 1, 0, 0 },
+			InTan { 1, 0, 0 },
+			OutTan { 1, 0, 0 },
+	}
+	GeosetId 0,
+}
+Light "Omni01" {
+	ObjectId 2,
+	Omnidirectional,
+	static AttenuationStart 80,
+	static AttenuationEnd 200,
+	Intensity 1 {
+		DontInterp,
+		0: 2,
+	}
+	static Color { 1, 1, 1 },
+	static AmbIntensity 0,
+	static AmbColor { 1, 1, 1 },
+}
+Helper "Helper01" {
+	ObjectId 3,
+	Scaling 1 {
+		Linear,
+		0: { 1, 1, 2 },
+	}
+}
+Attachment "Origin Ref" {
+	ObjectId 4,
+	AttachmentID 0,
+	Visibility 1 {
+		DontInterp,
+		0: 1,
+	}
+}
+ParticleEmitter "Emitter01" {
+	ObjectId 5,
+	EmitterUsesMDL,
+	static EmissionRate 10,
+	static Gravity 0,
+	static Longitude 0,
+	Latitude 1 {
+		Linear,
+		0: 0.5,
+	}
+	Particle {
+		static LifeSpan 1,
+		static InitVelocity 5,
+		Path "Abilities\\Spells\\Fire.mdl",
+	}
+}
+ParticleEmitter2 "Emitter02" {
+	ObjectId 6,
+	static Speed 100,
+	static Variation 0,
+	static Latitude 0,
+	static Gravity 0,
+	Visibility 1 {
+		DontInterp,
+		0: 1,
+	}
+	LifeSpan 1,
+	static EmissionRate 20,
+	static Width 10,
+	static Length 10,
+	Blend,
+	Rows 1,
+	Columns 1,
+	Head,
+	TailLength 0,
+	Time 0.5,
+	SegmentColor {
+		Color { 1, 1, 1 },
+		Color { 1, 1, 1 },
+		Color { 1, 1, 1 },
+	}
+	Alpha { 255, 255, 0 },
+	ParticleScaling { 1, 1, 1 },
+	LifeSpanUVAnim { 0, 0, 1 },
+	DecayUVAnim { 0, 0, 1 },
+	TailUVAnim { 0, 0, 1 },
+	TailDecayUVAnim { 0, 0, 1 },
+	TextureID 0,
+}
+ParticleEmitterPopcorn "Popcorn01" {
+	ObjectId 7,
+	static LifeSpan 1,
+	EmissionRate 1 {
+		Linear,
+		0: 10,
+	}
+	static Speed 0,
+	static Color { 1, 1, 1 },
+	static Alpha 1,
+	Path "Objects\\Spawnmodels\\Fire.pkb",
+	AnimVisibilityGuide "Birth=on",
+}
+RibbonEmitter "Ribbon01" {
+	ObjectId 8,
+	static HeightAbove 10,
+	static HeightBelow 10,
+	static Alpha 1,
+	static Color { 1, 1, 1 },
+	TextureSlot 1 {
+		DontInterp,
+		0: 1,
+	}
+	EmissionRate 30,
+	LifeSpan 1,
+	Gravity 0,
+	Rows 1,
+	Columns 1,
+	MaterialID 0,
+}
+Camera "Camera01" {
+	Position { 0, -100, 50 },
+	Rotation 1 {
+		Linear,
+		0: 1,
+	}
+	FieldOfView 0.78,
+	FarClip 1000,
+	NearClip 8,
+	Target {
+		Position { 0, 0, 50 },
+		Translation 1 {
+			Linear,
+			0: { 0, 0, 1 },
+		}
+	}
+}
+CollisionShape "Collision01" {
+	ObjectId 9,
+	Sphere,
+	Vertices 1 {
+		{ 0, 0, 0 },
+	}
+	BoundsRadius 42,
+}
+FaceFX "Arthas" {
+	Path "Units\\Human\\Arthas\\Arthas.facefx",
+}
+BindPose {
+	Matrices 1 {
+		{ 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0 },
+	}
+}
+"#;
+
+    // Valid files for the mutation tests, one per layout version, with every chunk type
+    fn fuzz_seeds() -> Vec<Vec<u8>> {
+        [800, 900, 1000, 1100]
+            .iter()
+            .map(|&version| {
+                // Version 1100 layers can't come from MDL, they're filled in below
+                let mut text = MDL_MODEL.replace(
+                    "FormatVersion 800",
+                    &format!("FormatVersion {}", version.min(1000)),
+                ) + FUZZ_MDL;
+                if version > 800 {
+                    let skin = "\t\t0, 0, 0, 0, 255, 0, 0, 0,\n".repeat(3);
+                    let tangents = format!(
+                        "\tTangents 3 {{\n{}\t}}\n\tSkinWeights 3 {{\n{}\t}}\n\tFaces",
+                        "\t\t{ 1, 0, 0, 1 },\n".repeat(3),
+                        skin
+                    );
+                    text = text.replacen("\tFaces", &tangents, 1);
+                }
+                let mut model = MDLXModel::read_mdl_file(&text).unwrap();
+                if version > 1000 {
+                    model.version_chunk.as_mut().unwrap().version = version;
+                    // Texture list of one texture with a KMTF block, kept raw
+                    let mut unknown_bytes = words(&[1, 0, 0, KMTF_TAG, 1, 0, 0xFFFF_FFFF, 0, 0]);
+                    unknown_bytes.extend(words(&[KMTA_TAG, 1, 0, 0xFFFF_FFFF, 0, 0]));
+                    for material in &mut model.material_chunk.as_mut().unwrap().data {
+                        for layer in &mut material.layers {
+                            layer.alpha_transform = None;
+                            layer.unknown_bytes = Some(unknown_bytes.clone());
+                        }
+                    }
+                }
+                model.unknown_chunks.push(UnknownChunk {
+                    tag: TXST_TAG,
+                    bytes: words(&[1, 1, 0]),
+                });
+                MDLXModel::write_mdx_file(model).unwrap()
+            })
+            .collect()
+    }
+
+    // Runs every decoder of the index, true if each found and read its chunk
+    fn decode_index(index: &MdxIndex) -> bool {
+        let results = [
+            index.version().is_ok(),
+            matches!(index.model(), Ok(Some(_))),
+            matches!(index.sequences(), Ok(Some(_))),
+            matches!(index.global_sequences(), Ok(Some(_))),
+            matches!(index.textures(), Ok(Some(_))),
+            matches!(index.texture_animations(), Ok(Some(_))),
+            matches!(index.geosets(), Ok(Some(_))),
+            matches!(index.geoset_animations(), Ok(Some(_))),
+            matches!(index.bones(), Ok(Some(_))),
+            matches!(index.materials(), Ok(Some(_))),
+            matches!(index.lights(), Ok(Some(_))),
+            matches!(index.helpers(), Ok(Some(_))),
+            matches!(index.attachments(), Ok(Some(_))),
+            matches!(index.pivot_points(), Ok(Some(_))),
+            matches!(index.particle_emitters(), Ok(Some(_))),
+            matches!(index.particle_emitters2(), Ok(Some(_))),
+            matches!(index.popcorn_emitters(), Ok(Some(_))),
+            matches!(index.ribbon_emitters(), Ok(Some(_))),
+            matches!(index.cameras(), Ok(Some(_))),
+            matches!(index.event_objects(), Ok(Some(_))),
+            matches!(index.collision_shapes(), Ok(Some(_))),
+            matches!(index.face_effects(), Ok(Some(_))),
+            matches!(index.bind_pose(), Ok(Some(_))),
+        ];
+        results.iter().all(|&result| result)
+    }
+
+    // Every reader has to turn broken input into an error
+    fn read_everything(bytes: &[u8]) {
+        let _ = MDLXModel::read_mdx_file(bytes.to_vec());
+        let _ = MDLXModel::read_from(bytes);
+        let _ = MDLXModel::read_from_seekable(std::io::Cursor::new(bytes));
+        if let Ok(index) = MdxIndex::new(bytes) {
+            decode_index(&index);
+        }
+    }
+
+    #[test]
+    fn fuzz_seeds_hold_every_chunk() {
+        init();
+
+        for bytes in fuzz_seeds() {
+            let model = MDLXModel::read_mdx_file(bytes.clone()).unwrap();
+            // All typed chunks and TXST, which is kept unknown
+            assert_eq!(model.chunk_order.len(), 24);
+            assert_eq!(model.unknown_chunks.len(), 1);
+            assert!(decode_index(&MdxIndex::new(&bytes).unwrap()));
+            assert_eq!(MDLXModel::read_from(&bytes[..]).unwrap(), model);
+            let cursor = std::io::Cursor::new(&bytes);
+            assert_eq!(MDLXModel::read_from_seekable(cursor).unwrap(), model);
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn mutated_files_never_panic(
+            seed in 0usize..4,
+            changes in proptest::collection::vec((proptest::num::usize::ANY, proptest::num::u8::ANY), 1..8),
+        ) {
+            let mut bytes = fuzz_seeds().swap_remove(seed);
+            for (position, value) in changes {
+                let position = position % bytes.len();
+                bytes[position] = value;
+            }
+            read_everything(&bytes);
+        }
+
+        #[test]
+        fn mutated_sizes_never_panic(
+            seed in 0usize..4,
+            position in proptest::num::usize::ANY,
+            value in proptest::prop_oneof![
+                proptest::strategy::Just(0u32),
+                proptest::strategy::Just(1u32),
+                proptest::strategy::Just(0x7fff_ffffu32),
+                proptest::strategy::Just(0x8000_0000u32),
+                proptest::strategy::Just(u32::MAX),
+                proptest::num::u32::ANY,
+            ],
+        ) {
+            let mut bytes = fuzz_seeds().swap_remove(seed);
+            // Counts and sizes are aligned u32 fields
+            let position = (position % (bytes.len() / 4)) * 4;
+            bytes[position..position + 4].copy_from_slice(&value.to_le_bytes());
+            read_everything(&bytes);
+        }
+
+        #[test]
+        fn truncated_files_never_panic(seed in 0usize..4, length in proptest::num::usize::ANY) {
+            let bytes = fuzz_seeds().swap_remove(seed);
+            read_everything(&bytes[..length % bytes.len()]);
+        }
+
+        #[test]
+        fn random_chunks_never_panic(body in proptest::collection::vec(proptest::num::u8::ANY, 0..512)) {
+            let mut bytes = b"MDLX".to_vec();
+            bytes.extend(body);
+            read_everything(&bytes);
+        }
+    }
+
+    #[test]
+    fn read_mdx_file_errors() {
+        init();
+
+        let error = MDLXModel::read_mdx_file(b"MDLXVERS".to_vec()).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(VERS_TAG)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, 8);
+
+        let error = MDLXModel::read_mdx_file(b"MDLXABCD\x08\0\0\0abcd".to_vec()).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(0x4443_4241)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+
+        let geoset = MDL_MODEL.find("Geoset {").unwrap();
+        let bone = MDL_MODEL.find("Bone \"Root\"").unwrap();
+        let model = MDLXModel::read_mdl_file(&MDL_MODEL[geoset..bone]).unwrap();
+        let mut bytes = MDLXModel::write_mdx_file(model).unwrap();
+        let texture_coordinate_set = bytes.len() - (4 + 3 * 8);
+        bytes.truncate(bytes.len() - 4);
+
+        let error = MDLXModel::read_mdx_file(bytes).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(GEOS_TAG)));
+        assert_eq!(error.path, vec!["geoset 0", "UVBS 0"]);
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, texture_coordinate_set);
+
+        // A cut off track tag is reported where it starts
+        let model = MDLXModel {
+            particle_emitter_chunk: Some(ParticleEmitterChunk {
+                data: vec![ParticleEmitter {
+                    node: node("Emitter01", 0),
+                    emission_rate: 0.0,
+                    gravity: 0.0,
+                    longitude: 0.0,
+                    latitude: 0.0,
+                    path: String::new(),
+                    life_span: 0.0,
+                    speed: 0.0,
+                    emission_rate_transform: None,
+                    gravity_transform: None,
+                    longitude_transform: None,
+                    latitude_transform: None,
+                    life_span_transform: None,
+                    speed_transform: None,
+                    visibility_transform: None,
+                    unknown_tracks: None,
+                }],
+            }),
+            ..Default::default()
+        };
+        let mut bytes = MDLXModel::write_mdx_file(model).unwrap();
+        let chunk = bytes.windows(4).position(|tag| tag == b"PREM").unwrap();
+        for size in &[chunk + 4, chunk + 8] {
+            let mut value = [0; 4];
+            value.copy_from_slice(&bytes[*size..*size + 4]);
+            let value = u32::from_le_bytes(value) + 2;
+            bytes[*size..*size + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        let track_tag = bytes.len();
+        bytes.extend_from_slice(b"KP");
+
+        let error = MDLXModel::read_mdx_file(bytes).unwrap_err();
+        assert_eq!(error.tag, Some(FourCC(PREM_TAG)));
+        assert_eq!(error.kind, MdxErrorKind::UnexpectedEof);
+        assert_eq!(error.offset, track_tag);
+    }
 }
@@ -0,0 +1,105 @@
+use std::fmt;
+
+pub use parser::read_mdl;
+pub use writer::write_mdl;
+
+mod parser;
+mod tokenizer;
+mod writer;
+
+// Keyword tables shared by the reader and the writer
+pub(crate) const NODE_FLAGS: &[(&str, u32)] = &[
+    ("Billboarded", 0x8),
+    ("BillboardedLockX", 0x10),
+    ("BillboardedLockY", 0x20),
+    ("BillboardedLockZ", 0x40),
+    ("CameraAnchored", 0x80),
+];
+
+pub(crate) const DONT_INHERIT_FLAGS: &[(&str, u32)] =
+    &[("Translation", 0x1), ("Rotation", 0x2), ("Scaling", 0x4)];
+
+pub(crate) const PARTICLE_EMITTER_FLAGS: &[(&str, u32)] =
+    &[("EmitterUsesMDL", 0x8000), ("EmitterUsesTGA", 0x10000)];
+
+pub(crate) const PARTICLE_EMITTER2_FLAGS: &[(&str, u32)] = &[
+    ("Unshaded", 0x8000),
+    ("SortPrimsFarZ", 0x10000),
+    ("LineEmitter", 0x20000),
+    ("Unfogged", 0x40000),
+    ("ModelSpace", 0x80000),
+    ("XYQuad", 0x100000),
+];
+
+pub(crate) const MATERIAL_FLAGS: &[(&str, u32)] = &[
+    ("ConstantColor", 0x1),
+    ("SortPrimsFarZ", 0x10),
+    ("FullResolution", 0x20),
+];
+
+pub(crate) const LAYER_SHADING_FLAGS: &[(&str, u32)] = &[
+    ("Unshaded", 0x1),
+    ("SphereEnvMap", 0x2),
+    ("TwoSided", 0x10),
+    ("Unfogged", 0x20),
+    ("NoDepthTest", 0x40),
+    ("NoDepthSet", 0x80),
+    ("Unlit", 0x100),
+];
+
+pub(crate) const LAYER_FILTER_MODES: &[&str] = &[
+    "None",
+    "Transparent",
+    "Blend",
+    "Additive",
+    "AddAlpha",
+    "Modulate",
+    "Modulate2x",
+];
+
+pub(crate) const PARTICLE_EMITTER2_FILTER_MODES: &[&str] =
+    &["Blend", "Additive", "Modulate", "Modulate2x", "AlphaKey"];
+
+pub(crate) const PARTICLE_EMITTER2_HEAD_OR_TAIL: &[&str] = &["Head", "Tail", "Both"];
+
+pub(crate) const LIGHT_TYPES: &[&str] = &["Omnidirectional", "Directional", "Ambient"];
+
+pub(crate) const FACE_TYPES: &[&str] = &[
+    "Points",
+    "Lines",
+    "LineLoop",
+    "LineStrip",
+    "Triangles",
+    "TriangleStrip",
+    "TriangleFan",
+    "Quads",
+    "QuadStrip",
+    "Polygons",
+];
+
+pub(crate) const TEXTURE_FLAGS: &[(&str, u32)] = &[("WrapWidth", 0x1), ("WrapHeight", 0x2)];
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct MdlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl MdlError {
+    pub fn new<S: Into<String>>(line: usize, column: usize, message: S) -> Self {
+        MdlError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MdlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for MdlError {}
@@ -0,0 +1,1608 @@
+use crate::chunks::*;
+use crate::mdl::tokenizer::{Token, TokenKind, Tokenizer};
+use crate::mdl::*;
+use crate::MDLXModel;
+
+// Generic MDL syntax tree. Every statement is a list of atoms (keywords, strings, numbers
+// and colons) optionally followed by a braced block of nested statements.
+#[derive(Debug)]
+struct Statement {
+    items: Vec<Token>,
+    block: Option<Vec<Statement>>,
+    line: usize,
+    column: usize,
+}
+
+impl Statement {
+    fn error<S: Into<String>>(&self, message: S) -> MdlError {
+        MdlError::new(self.line, self.column, message)
+    }
+
+    fn is_static(&self) -> bool {
+        match self.items.first() {
+            Some(Token {
+                kind: TokenKind::Ident(ident),
+                ..
+            }) => ident == "static",
+            _ => false,
+        }
+    }
+
+    fn keyword(&self) -> Option<&str> {
+        let index = if self.is_static() { 1 } else { 0 };
+        match self.items.get(index) {
+            Some(Token {
+                kind: TokenKind::Ident(ident),
+                ..
+            }) => Some(ident.as_str()),
+            _ => None,
+        }
+    }
+
+    // Animated properties look like `Alpha 2 { Linear, ... }`.
+    fn is_animated(&self) -> bool {
+        !self.is_static()
+            && self.block.is_some()
+            && match self.items.get(1) {
+                Some(token) => matches!(token.kind, TokenKind::Number(_)),
+                None => false,
+            }
+    }
+
+    fn block(&self) -> Result<&[Statement], MdlError> {
+        match &self.block {
+            Some(block) => Ok(block),
+            None => Err(self.error("Expected '{'")),
+        }
+    }
+
+    fn string(&self) -> Result<String, MdlError> {
+        self.items
+            .iter()
+            .find_map(|token| match &token.kind {
+                TokenKind::Str(value) => Some(value.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| self.error("Expected string"))
+    }
+
+    fn last_item(&self) -> Result<&Token, MdlError> {
+        match self.items.last() {
+            Some(token) if self.items.len() > 1 || self.keyword().is_none() => Ok(token),
+            _ => Err(self.error("Expected value")),
+        }
+    }
+
+    fn number<T: FromMdl>(&self) -> Result<T, MdlError> {
+        T::from_token(self.last_item()?)
+    }
+
+    fn numbers<T: FromMdl>(&self) -> Result<Vec<T>, MdlError> {
+        let mut result = Vec::new();
+        for statement in self.block()? {
+            if statement.block.is_some() || statement.items.len() != 1 {
+                return Err(statement.error("Expected number"));
+            }
+            result.push(T::from_token(&statement.items[0])?);
+        }
+        Ok(result)
+    }
+
+    fn fixed_numbers<T: FromMdl>(&self, count: usize) -> Result<Vec<T>, MdlError> {
+        let result = self.numbers::<T>()?;
+        if result.len() != count {
+            return Err(self.error(format!(
+                "Expected {} values - Found {}",
+                count,
+                result.len()
+            )));
+        }
+        Ok(result)
+    }
+
+    fn unknown(&self, context: &str) -> MdlError {
+        match self.keyword() {
+            Some(keyword) => self.error(format!("Unknown {} property '{}'", context, keyword)),
+            None => self.error(format!("Unexpected value in {}", context)),
+        }
+    }
+}
+
+fn token_error<S: Into<String>>(token: &Token, message: S) -> MdlError {
+    MdlError::new(token.line, token.column, message)
+}
+
+trait FromMdl: Sized {
+    fn from_token(token: &Token) -> Result<Self, MdlError> {
+        Err(token_error(token, "Expected block"))
+    }
+
+    fn from_statement(statement: &Statement) -> Result<Self, MdlError> {
+        statement.number::<Self>()
+    }
+}
+
+impl FromMdl for f32 {
+    fn from_token(token: &Token) -> Result<Self, MdlError> {
+        match &token.kind {
+            TokenKind::Number(value) | TokenKind::Ident(value) => value
+                .parse::<f32>()
+                .map_err(|_| token_error(token, format!("Invalid float '{}'", value))),
+            _ => Err(token_error(token, "Expected float")),
+        }
+    }
+}
+
+impl FromMdl for u32 {
+    fn from_token(token: &Token) -> Result<Self, MdlError> {
+        match &token.kind {
+            // Negative ids are stored as two's complement, -1 means none
+            TokenKind::Number(value) => match value.parse::<i64>() {
+                Ok(number) if number >= i64::from(i32::MIN) && number <= i64::from(u32::MAX) => {
+                    Ok(number as u32)
+                }
+                _ => Err(token_error(token, format!("Invalid integer '{}'", value))),
+            },
+            _ => Err(token_error(token, "Expected integer")),
+        }
+    }
+}
+
+impl FromMdl for u8 {
+    fn from_token(token: &Token) -> Result<Self, MdlError> {
+        match &token.kind {
+            TokenKind::Number(value) => value
+                .parse::<u8>()
+                .map_err(|_| token_error(token, format!("Invalid byte '{}'", value))),
+            _ => Err(token_error(token, "Expected integer")),
+        }
+    }
+}
+
+impl FromMdl for Vec2 {
+    fn from_statement(statement: &Statement) -> Result<Self, MdlError> {
+        let v = statement.fixed_numbers::<f32>(2)?;
+        Ok(Vec2 { x: v[0], y: v[1] })
+    }
+}
+
+impl FromMdl for Vec3 {
+    fn from_statement(statement: &Statement) -> Result<Self, MdlError> {
+        let v = statement.fixed_numbers::<f32>(3)?;
+        Ok(Vec3 {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+        })
+    }
+}
+
+impl FromMdl for Vec4 {
+    fn from_statement(statement: &Statement) -> Result<Self, MdlError> {
+        let v = statement.fixed_numbers::<f32>(4)?;
+        Ok(Vec4 {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+            w: v[3],
+        })
+    }
+}
+
+impl FromMdl for Color {
+    fn from_statement(statement: &Statement) -> Result<Self, MdlError> {
+        let v = statement.fixed_numbers::<f32>(3)?;
+        Ok(Color {
+            b: v[0],
+            g: v[1],
+            r: v[2],
+        })
+    }
+}
+
+fn parse_statements(
+    tokens: &[Token],
+    pos: &mut usize,
+    open: Option<&Token>,
+) -> Result<Vec<Statement>, MdlError> {
+    let mut statements = Vec::new();
+
+    loop {
+        let token = match tokens.get(*pos) {
+            Some(token) => token,
+            None => match open {
+                Some(open) => return Err(token_error(open, "Unclosed '{'")),
+                None => return Ok(statements),
+            },
+        };
+
+        match token.kind {
+            TokenKind::CloseBrace => {
+                if open.is_none() {
+                    return Err(token_error(token, "Unexpected '}'"));
+                }
+                *pos += 1;
+                return Ok(statements);
+            }
+            TokenKind::Comma => {
+                *pos += 1;
+            }
+            _ => {
+                let mut statement = Statement {
+                    items: Vec::new(),
+                    block: None,
+                    line: token.line,
+                    column: token.column,
+                };
+
+                while let Some(token) = tokens.get(*pos) {
+                    match token.kind {
+                        TokenKind::Comma => {
+                            *pos += 1;
+                            break;
+                        }
+                        TokenKind::CloseBrace => break,
+                        TokenKind::OpenBrace => {
+                            *pos += 1;
+                            statement.block = Some(parse_statements(tokens, pos, Some(token))?);
+                            if let Some(Token {
+                                kind: TokenKind::Comma,
+                                ..
+                            }) = tokens.get(*pos)
+                            {
+                                *pos += 1;
+                            }
+                            break;
+                        }
+                        _ => {
+                            statement.items.push(token.clone());
+                            *pos += 1;
+                        }
+                    }
+                }
+
+                statements.push(statement);
+            }
+        }
+    }
+}
+
+fn parse_transform<T: FromMdl>(statement: &Statement) -> Result<Transform<T>, MdlError> {
+    let mut interpolation = None;
+    let mut global_sequence_id = None;
+    // Keys with the tangents that followed them
+    let mut keys: Vec<(u32, T, Option<T>, Option<T>)> = Vec::new();
+
+    for s in statement.block()? {
+        match s.keyword() {
+            Some("DontInterp") => interpolation = Some(Interpolation::DontInterp),
+            Some("Linear") => interpolation = Some(Interpolation::Linear),
+            Some("Hermite") => interpolation = Some(Interpolation::Hermite),
+            Some("Bezier") => interpolation = Some(Interpolation::Bezier),
+            Some("GlobalSeqId") => global_sequence_id = Some(GlobalSequenceId(s.number()?)),
+            Some("InTan") => match keys.last_mut() {
+                Some(key) => key.2 = Some(T::from_statement(s)?),
+                None => return Err(s.error("InTan without key")),
+            },
+            Some("OutTan") => match keys.last_mut() {
+                Some(key) => key.3 = Some(T::from_statement(s)?),
+                None => return Err(s.error("OutTan without key")),
+            },
+            Some(_) => return Err(s.unknown("track")),
+            None => {
+                let time = match (s.items.first(), s.items.get(1)) {
+                    (Some(time), Some(colon)) if colon.kind == TokenKind::Colon => {
+                        u32::from_token(time)?
+                    }
+                    _ => return Err(s.error("Expected key 'time: value'")),
+                };
+                keys.push((time, T::from_statement(s)?, None, None));
+            }
+        }
+    }
+
+    let interpolation = match interpolation {
+        Some(interpolation) => interpolation,
+        None => return Err(statement.error("Missing interpolation type")),
+    };
+
+    let tracks = if interpolation.has_tangents() {
+        let mut tracks = Vec::new();
+        for (time, value, in_tan, out_tan) in keys {
+            match (in_tan, out_tan) {
+                (Some(in_tan), Some(out_tan)) => tracks.push(TangentTrack {
+                    time,
+                    value,
+                    in_tan,
+                    out_tan,
+                }),
+                _ => return Err(statement.error(format!("Key {} is missing tangents", time))),
+            }
+        }
+        match interpolation {
+            Interpolation::Hermite => Tracks::Hermite(tracks),
+            _ => Tracks::Bezier(tracks),
+        }
+    } else {
+        let mut tracks = Vec::new();
+        for (time, value, in_tan, out_tan) in keys {
+            if in_tan.is_some() || out_tan.is_some() {
+                return Err(statement.error(format!(
+                    "Key {} has tangents without hermite or bezier interpolation",
+                    time
+                )));
+            }
+            tracks.push(Track { time, value });
+        }
+        match interpolation {
+            Interpolation::DontInterp => Tracks::DontInterp(tracks),
+            _ => Tracks::Linear(tracks),
+        }
+    };
+
+    Ok(Transform {
+        global_sequence_id,
+        tracks,
+    })
+}
+
+fn parse_property<T: FromMdl>(
+    statement: &Statement,
+    value: &mut T,
+    transform: &mut Option<Transform<T>>,
+) -> Result<(), MdlError> {
+    if statement.is_animated() {
+        *transform = Some(parse_transform(statement)?);
+    } else {
+        *value = T::from_statement(statement)?;
+    }
+    Ok(())
+}
+
+fn parse_flag(statement: &Statement, names: &[(&str, u32)], flags: &mut u32) -> bool {
+    match names
+        .iter()
+        .find(|(name, _)| Some(*name) == statement.keyword())
+    {
+        Some((_, flag)) => {
+            *flags |= flag;
+            true
+        }
+        None => false,
+    }
+}
+
+fn parse_enum(statement: &Statement, names: &[&str], value: &mut u32) -> bool {
+    match names
+        .iter()
+        .position(|name| Some(*name) == statement.keyword())
+    {
+        Some(index) => {
+            *value = index as u32;
+            true
+        }
+        None => false,
+    }
+}
+
+fn parse_extent_property(statement: &Statement, extent: &mut Extent) -> Result<bool, MdlError> {
+    match statement.keyword() {
+        Some("MinimumExtent") => extent.minimum = Vec3::from_statement(statement)?,
+        Some("MaximumExtent") => extent.maximum = Vec3::from_statement(statement)?,
+        Some("BoundsRadius") => extent.bounds_radius = statement.number()?,
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn empty_lod() -> GeosetLod {
+    GeosetLod {
+        lod: 0,
+        lod_name: String::new(),
+    }
+}
+
+fn empty_extent() -> Extent {
+    let zero = || Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    Extent {
+        bounds_radius: 0.0,
+        minimum: zero(),
+        maximum: zero(),
+    }
+}
+
+struct ModelParser {
+    model: MDLXModel,
+    node_count: u32,
+}
+
+impl ModelParser {
+    fn new_node(&mut self, statement: &Statement, type_flag: u32) -> Result<Node, MdlError> {
+        let node = Node {
+            name: statement.string()?,
+            object_id: self.node_count,
+            parent_id: 0xFFFF_FFFF,
+            flags: type_flag,
+            translation: None,
+            rotation: None,
+            scaling: None,
+            unknown_tracks: None,
+        };
+        self.node_count += 1;
+        Ok(node)
+    }
+
+    fn parse_node_property(
+        node: &mut Node,
+        statement: &Statement,
+        flags: &[(&str, u32)],
+    ) -> Result<bool, MdlError> {
+        match statement.keyword() {
+            Some("ObjectId") => node.object_id = statement.number()?,
+            Some("Parent") => node.parent_id = statement.number()?,
+            Some("Translation") => node.translation = Some(parse_transform(statement)?),
+            Some("Rotation") => node.rotation = Some(parse_transform(statement)?),
+            Some("Scaling") => node.scaling = Some(parse_transform(statement)?),
+            Some("DontInherit") => {
+                for s in statement.block()? {
+                    if !parse_flag(s, DONT_INHERIT_FLAGS, &mut node.flags) {
+                        return Err(s.unknown("DontInherit"));
+                    }
+                }
+            }
+            _ => {
+                return Ok(parse_flag(statement, NODE_FLAGS, &mut node.flags)
+                    || parse_flag(statement, flags, &mut node.flags))
+            }
+        }
+        Ok(true)
+    }
+
+    fn parse(&mut self, statements: &[Statement]) -> Result<(), MdlError> {
+        for statement in statements {
+            match statement.keyword() {
+                Some("Version") => self.parse_version(statement)?,
+                Some("Model") => self.parse_model(statement)?,
+                Some("Sequences") => self.parse_sequences(statement)?,
+                Some("GlobalSequences") => self.parse_global_sequences(statement)?,
+                Some("Textures") => self.parse_textures(statement)?,
+                Some("Materials") => self.parse_materials(statement)?,
+                Some("TextureAnims") => self.parse_texture_animations(statement)?,
+                Some("Geoset") => self.parse_geoset(statement)?,
+                Some("GeosetAnim") => self.parse_geoset_animation(statement)?,
+                Some("Bone") => self.parse_bone(statement)?,
+                Some("Light") => self.parse_light(statement)?,
+                Some("Helper") => self.parse_helper(statement)?,
+                Some("Attachment") => self.parse_attachment(statement)?,
+                Some("PivotPoints") => self.parse_pivot_points(statement)?,
+                Some("ParticleEmitter") => self.parse_particle_emitter(statement)?,
+                Some("ParticleEmitter2") => self.parse_particle_emitter2(statement)?,
+                Some("ParticleEmitterPopcorn") => self.parse_popcorn_emitter(statement)?,
+                Some("RibbonEmitter") => self.parse_ribbon_emitter(statement)?,
+                Some("EventObject") => self.parse_event_object(statement)?,
+                Some("Camera") => self.parse_camera(statement)?,
+                Some("CollisionShape") => self.parse_collision_shape(statement)?,
+                Some("FaceFX") => self.parse_face_effect(statement)?,
+                Some("BindPose") => self.parse_bind_pose(statement)?,
+                Some(keyword) => {
+                    return Err(statement.error(format!("Unknown block '{}'", keyword)))
+                }
+                None => return Err(statement.error("Expected block name")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_version(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut version = 800;
+        for s in statement.block()? {
+            match s.keyword() {
+                Some("FormatVersion") => version = s.number()?,
+                _ => return Err(s.unknown("Version")),
+            }
+        }
+        self.model.version_chunk = Some(VersionChunk { version });
+        Ok(())
+    }
+
+    fn parse_model(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut model = ModelChunk {
+            name: statement.string()?,
+            unknown: 0,
+            extent: empty_extent(),
+            blend_time: 0,
+        };
+        for s in statement.block()? {
+            if parse_extent_property(s, &mut model.extent)? {
+                continue;
+            }
+            match s.keyword() {
+                Some("BlendTime") => model.blend_time = s.number()?,
+                // Object counts are derived from the chunks themselves
+                Some(keyword) if keyword.starts_with("Num") => {}
+                _ => return Err(s.unknown("Model")),
+            }
+        }
+        self.model.model_chunk = Some(model);
+        Ok(())
+    }
+
+    fn parse_sequences(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for anim in statement.block()? {
+            if anim.keyword() != Some("Anim") {
+                return Err(anim.unknown("Sequences"));
+            }
+            let mut sequence = Sequence {
+                name: anim.string()?,
+                interval_start: 0,
+                interval_end: 0,
+                move_speed: 0.0,
+                non_looping: 0,
+                rarity: 0.0,
+                unknown: 0,
+                extent: empty_extent(),
+            };
+            for s in anim.block()? {
+                if parse_extent_property(s, &mut sequence.extent)? {
+                    continue;
+                }
+                match s.keyword() {
+                    Some("Interval") => {
+                        let interval = s.fixed_numbers::<u32>(2)?;
+                        sequence.interval_start = interval[0];
+                        sequence.interval_end = interval[1];
+                    }
+                    Some("NonLooping") => sequence.non_looping = 1,
+                    Some("MoveSpeed") => sequence.move_speed = s.number()?,
+                    Some("Rarity") => sequence.rarity = s.number()?,
+                    _ => return Err(s.unknown("Anim")),
+                }
+            }
+            data.push(sequence);
+        }
+        self.model.sequence_chunk = Some(SequenceChunk { data });
+        Ok(())
+    }
+
+    fn parse_global_sequences(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for s in statement.block()? {
+            match s.keyword() {
+                Some("Duration") => data.push(GlobalSequence {
+                    duration: s.number()?,
+                }),
+                _ => return Err(s.unknown("GlobalSequences")),
+            }
+        }
+        self.model.global_sequence_chunk = Some(GlobalSequenceChunk { data });
+        Ok(())
+    }
+
+    fn parse_textures(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for bitmap in statement.block()? {
+            if bitmap.keyword() != Some("Bitmap") {
+                return Err(bitmap.unknown("Textures"));
+            }
+            let mut texture = Texture {
+                replaceable_id: 0,
+                file_name: String::new(),
+                unknown: 0,
+                flags: 0,
+            };
+            for s in bitmap.block()? {
+                if parse_flag(s, TEXTURE_FLAGS, &mut texture.flags) {
+                    continue;
+                }
+                match s.keyword() {
+                    Some("Image") => texture.file_name = s.string()?,
+                    Some("ReplaceableId") => texture.replaceable_id = s.number()?,
+                    _ => return Err(s.unknown("Bitmap")),
+                }
+            }
+            data.push(texture);
+        }
+        self.model.texture_chunk = Some(TextureChunk { data });
+        Ok(())
+    }
+
+    fn parse_materials(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for material_statement in statement.block()? {
+            if material_statement.keyword() != Some("Material") {
+                return Err(material_statement.unknown("Materials"));
+            }
+            let mut material = Material {
+                priority_plane: 0,
+                flags: 0,
+                shader: None,
+                layers: Vec::new(),
+            };
+            for s in material_statement.block()? {
+                if parse_flag(s, MATERIAL_FLAGS, &mut material.flags) {
+                    continue;
+                }
+                match s.keyword() {
+                    Some("PriorityPlane") => material.priority_plane = s.number()?,
+                    Some("Shader") => material.shader = Some(s.string()?),
+                    Some("Layer") => material.layers.push(Self::parse_layer(s)?),
+                    _ => return Err(s.unknown("Material")),
+                }
+            }
+            data.push(material);
+        }
+        self.model.material_chunk = Some(MaterialChunk { data });
+        Ok(())
+    }
+
+    fn parse_layer(statement: &Statement) -> Result<Layer, MdlError> {
+        let mut layer = Layer {
+            filter_mode: 0,
+            shading_flags: 0,
+            texture_id: 0,
+            texture_animation_id: 0xFFFF_FFFF,
+            coord_id: 0,
+            alpha: 1.0,
+            hd: None,
+            unknown_bytes: None,
+            texture_id_transform: None,
+            alpha_transform: None,
+            emissive_gain_transform: None,
+            fresnel_color_transform: None,
+            fresnel_alpha_transform: None,
+            fresnel_team_color_transform: None,
+            unknown_tracks: None,
+        };
+        for s in statement.block()? {
+            if parse_flag(s, LAYER_SHADING_FLAGS, &mut layer.shading_flags) {
+                continue;
+            }
+            match s.keyword() {
+                Some("FilterMode") => {
+                    let mode = match s.items.get(1) {
+                        Some(Token {
+                            kind: TokenKind::Ident(mode),
+                            ..
+                        }) => mode.as_str(),
+                        _ => return Err(s.error("Expected filter mode")),
+                    };
+                    match LAYER_FILTER_MODES.iter().position(|name| *name == mode) {
+                        Some(index) => layer.filter_mode = index as u32,
+                        None => return Err(s.error(format!("Unknown filter mode '{}'", mode))),
+                    }
+                }
+                Some("TextureID") => {
+                    parse_property(s, &mut layer.texture_id, &mut layer.texture_id_transform)?
+                }
+                Some("Alpha") => parse_property(s, &mut layer.alpha, &mut layer.alpha_transform)?,
+                Some("TVertexAnimId") => layer.texture_animation_id = s.number()?,
+                Some("CoordId") => layer.coord_id = s.number()?,
+                Some("EmissiveGain") => parse_property(
+                    s,
+                    &mut layer.hd.get_or_insert_with(LayerHd::default).emissive_gain,
+                    &mut layer.emissive_gain_transform,
+                )?,
+                Some("FresnelColor") => parse_property(
+                    s,
+                    &mut layer.hd.get_or_insert_with(LayerHd::default).fresnel_color,
+                    &mut layer.fresnel_color_transform,
+                )?,
+                Some("FresnelOpacity") => parse_property(
+                    s,
+                    &mut layer
+                        .hd
+                        .get_or_insert_with(LayerHd::default)
+                        .fresnel_opacity,
+                    &mut layer.fresnel_alpha_transform,
+                )?,
+                Some("FresnelTeamColor") => parse_property(
+                    s,
+                    &mut layer
+                        .hd
+                        .get_or_insert_with(LayerHd::default)
+                        .fresnel_team_color,
+                    &mut layer.fresnel_team_color_transform,
+                )?,
+                _ => return Err(s.unknown("Layer")),
+            }
+        }
+        Ok(layer)
+    }
+
+    fn parse_texture_animations(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for anim in statement.block()? {
+            if anim.keyword() != Some("TVertexAnim") {
+                return Err(anim.unknown("TextureAnims"));
+            }
+            let mut texture_animation = TextureAnimation {
+                translation: None,
+                rotation: None,
+                scaling: None,
+                unknown_tracks: None,
+            };
+            for s in anim.block()? {
+                match s.keyword() {
+                    Some("Translation") => {
+                        texture_animation.translation = Some(parse_transform(s)?)
+                    }
+                    Some("Rotation") => texture_animation.rotation = Some(parse_transform(s)?),
+                    Some("Scaling") => texture_animation.scaling = Some(parse_transform(s)?),
+                    _ => return Err(s.unknown("TVertexAnim")),
+                }
+            }
+            data.push(texture_animation);
+        }
+        self.model.texture_animation_chunk = Some(TextureAnimationChunk { data });
+        Ok(())
+    }
+
+    fn parse_geoset(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut geoset = Geoset {
+            vertex_positions: Vec::new(),
+            vertex_normals: Vec::new(),
+            face_type_groups: Vec::new(),
+            face_groups: Vec::new(),
+            faces: Vec::new(),
+            vertex_groups: Vec::new(),
+            matrix_groups: Vec::new(),
+            matrix_indexes: Vec::new(),
+            material_id: 0,
+            selection_group: 0,
+            selection_type: 0,
+            lod: None,
+            extent: empty_extent(),
+            extent_sequences: Vec::new(),
+            tangents: None,
+            skin: None,
+            texture_coordinate_sets: Vec::new(),
+        };
+
+        for s in statement.block()? {
+            if parse_extent_property(s, &mut geoset.extent)? {
+                continue;
+            }
+            match s.keyword() {
+                Some("Vertices") => {
+                    for v in s.block()? {
+                        let position = v.fixed_numbers::<f32>(3)?;
+                        geoset.vertex_positions.push(VertexPosition {
+                            position: [position[0], position[1], position[2]],
+                        });
+                    }
+                }
+                Some("Normals") => {
+                    for v in s.block()? {
+                        let normal = v.fixed_numbers::<f32>(3)?;
+                        geoset.vertex_normals.push(VertexNormal {
+                            normal: [normal[0], normal[1], normal[2]],
+                        });
+                    }
+                }
+                Some("TVertices") => {
+                    let mut texture_coordinates = Vec::new();
+                    for v in s.block()? {
+                        let uv = v.fixed_numbers::<f32>(2)?;
+                        texture_coordinates.push([uv[0], uv[1]]);
+                    }
+                    geoset.texture_coordinate_sets.push(TextureCoordinateSet {
+                        texture_coordinates,
+                    });
+                }
+                Some("Tangents") => {
+                    let mut tangents = Vec::new();
+                    for v in s.block()? {
+                        tangents.push(Vec4::from_statement(v)?);
+                    }
+                    geoset.tangents = Some(tangents);
+                }
+                Some("SkinWeights") => geoset.skin = Some(s.numbers::<u8>()?),
+                Some("VertexGroup") => {
+                    for matrix_group in s.numbers::<u8>()? {
+                        geoset.vertex_groups.push(VertexGroup { matrix_group });
+                    }
+                }
+                Some("Faces") => {
+                    let mut indexes = Vec::new();
+                    for primitives in s.block()? {
+                        let mut face_type = 0;
+                        if !parse_enum(primitives, FACE_TYPES, &mut face_type) {
+                            return Err(primitives.unknown("Faces"));
+                        }
+                        let mut number_of_indexes = 0;
+                        for group in primitives.block()? {
+                            let group_indexes = group.numbers::<u32>()?;
+                            number_of_indexes += group_indexes.len() as u32;
+                            for index in group_indexes {
+                                if index > u32::from(u16::MAX) {
+                                    return Err(group.error(format!("Invalid index {}", index)));
+                                }
+                                indexes.push(index as u16);
+                            }
+                        }
+                        geoset.face_type_groups.push(FaceTypeGroup { face_type });
+                        geoset.face_groups.push(FaceGroup { number_of_indexes });
+                    }
+                    if indexes.len() % 3 != 0 {
+                        return Err(s.error("Face indexes are not a multiple of 3"));
+                    }
+                    for face in indexes.chunks(3) {
+                        geoset.faces.push(Face {
+                            index1: face[0],
+                            index2: face[1],
+                            index3: face[2],
+                        });
+                    }
+                }
+                Some("Groups") => {
+                    for matrices in s.block()? {
+                        if matrices.keyword() != Some("Matrices") {
+                            return Err(matrices.unknown("Groups"));
+                        }
+                        let matrix_indexes = matrices.numbers::<u32>()?;
+                        geoset.matrix_groups.push(MatrixGroup {
+                            matrix_group_size: matrix_indexes.len() as u32,
+                        });
+                        for matrix_index in matrix_indexes {
+                            geoset.matrix_indexes.push(MatrixIndex { matrix_index });
+                        }
+                    }
+                }
+                Some("Anim") => {
+                    let mut extent = empty_extent();
+                    for e in s.block()? {
+                        if !parse_extent_property(e, &mut extent)? {
+                            return Err(e.unknown("Anim"));
+                        }
+                    }
+                    geoset.extent_sequences.push(extent);
+                }
+                Some("MaterialID") => geoset.material_id = s.number()?,
+                Some("SelectionGroup") => geoset.selection_group = s.number()?,
+                Some("Unselectable") => geoset.selection_type = 4,
+                Some("LevelOfDetail") => {
+                    geoset.lod.get_or_insert_with(empty_lod).lod = s.number()?;
+                }
+                Some("Name") => geoset.lod.get_or_insert_with(empty_lod).lod_name = s.string()?,
+                _ => return Err(s.unknown("Geoset")),
+            }
+        }
+
+        self.model
+            .geoset_chunk
+            .get_or_insert_with(|| GeosetChunk { data: Vec::new() })
+            .data
+            .push(geoset);
+        Ok(())
+    }
+
+    fn parse_geoset_animation(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut geoset_animation = GeosetAnimation {
+            alpha: 1.0,
+            flags: 0,
+            color: [1.0, 1.0, 1.0],
+            geoset_id: 0,
+            geoset_alpha: None,
+            geoset_color: None,
+            unknown_tracks: None,
+        };
+        for s in statement.block()? {
+            match s.keyword() {
+                Some("DropShadow") => geoset_animation.flags |= 0x1,
+                Some("Alpha") => parse_property(
+                    s,
+                    &mut geoset_animation.alpha,
+                    &mut geoset_animation.geoset_alpha,
+                )?,
+                Some("Color") => {
+                    geoset_animation.flags |= 0x2;
+                    if s.is_animated() {
+                        geoset_animation.geoset_color = Some(parse_transform(s)?);
+                    } else {
+                        let color = s.fixed_numbers::<f32>(3)?;
+                        geoset_animation.color = [color[0], color[1], color[2]];
+                    }
+                }
+                Some("GeosetId") => geoset_animation.geoset_id = s.number()?,
+                _ => return Err(s.unknown("GeosetAnim")),
+            }
+        }
+        self.model
+            .geoset_animation_chunk
+            .get_or_insert_with(|| GeosetAnimationChunk { data: Vec::new() })
+            .data
+            .push(geoset_animation);
+        Ok(())
+    }
+
+    fn parse_bone(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut bone = Bone {
+            node: self.new_node(statement, BONE_FLAG)?,
+            geoset_id: 0xFFFF_FFFF,
+            geoset_animation_id: 0xFFFF_FFFF,
+        };
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut bone.node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("GeosetId") => match s.items.get(1).map(|token| &token.kind) {
+                    Some(TokenKind::Ident(ident)) if ident == "Multiple" => {
+                        bone.geoset_id = 0xFFFF_FFFF
+                    }
+                    _ => bone.geoset_id = s.number()?,
+                },
+                Some("GeosetAnimId") => match s.items.get(1).map(|token| &token.kind) {
+                    Some(TokenKind::Ident(ident)) if ident == "None" => {
+                        bone.geoset_animation_id = 0xFFFF_FFFF
+                    }
+                    _ => bone.geoset_animation_id = s.number()?,
+                },
+                _ => return Err(s.unknown("Bone")),
+            }
+        }
+        self.model
+            .bone_chunk
+            .get_or_insert_with(|| BoneChunk { data: Vec::new() })
+            .data
+            .push(bone);
+        Ok(())
+    }
+
+    fn parse_light(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let white = || Color {
+            b: 1.0,
+            g: 1.0,
+            r: 1.0,
+        };
+        let mut light = Light {
+            node: self.new_node(statement, LIGHT_FLAG)?,
+            light_type: 0,
+            attenuation_start: 0.0,
+            attenuation_end: 0.0,
+            color: white(),
+            intensity: 0.0,
+            ambient_color: white(),
+            ambient_intensity: 0.0,
+            attenuation_start_transform: None,
+            attenuation_end_transform: None,
+            color_transform: None,
+            ambient_color_transform: None,
+            intensity_transform: None,
+            ambient_intensity_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut light.node, s, &[])?
+                || parse_enum(s, LIGHT_TYPES, &mut light.light_type)
+            {
+                continue;
+            }
+            match s.keyword() {
+                Some("AttenuationStart") if s.is_animated() => {
+                    light.attenuation_start_transform = Some(parse_transform(s)?)
+                }
+                Some("AttenuationStart") => light.attenuation_start = s.number()?,
+                Some("AttenuationEnd") if s.is_animated() => {
+                    light.attenuation_end_transform = Some(parse_transform(s)?)
+                }
+                Some("AttenuationEnd") => light.attenuation_end = s.number()?,
+                Some("Color") => parse_property(s, &mut light.color, &mut light.color_transform)?,
+                Some("Intensity") => {
+                    parse_property(s, &mut light.intensity, &mut light.intensity_transform)?
+                }
+                Some("AmbColor") => parse_property(
+                    s,
+                    &mut light.ambient_color,
+                    &mut light.ambient_color_transform,
+                )?,
+                Some("AmbIntensity") => parse_property(
+                    s,
+                    &mut light.ambient_intensity,
+                    &mut light.ambient_intensity_transform,
+                )?,
+                Some("Visibility") => light.visibility_transform = Some(parse_transform(s)?),
+                _ => return Err(s.unknown("Light")),
+            }
+        }
+        self.model
+            .light_chunk
+            .get_or_insert_with(|| LightChunk { data: Vec::new() })
+            .data
+            .push(light);
+        Ok(())
+    }
+
+    fn parse_helper(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut helper = Helper {
+            node: self.new_node(statement, HELPER_FLAG)?,
+        };
+        for s in statement.block()? {
+            if !Self::parse_node_property(&mut helper.node, s, &[])? {
+                return Err(s.unknown("Helper"));
+            }
+        }
+        self.model
+            .helper_chunk
+            .get_or_insert_with(|| HelperChunk { data: Vec::new() })
+            .data
+            .push(helper);
+        Ok(())
+    }
+
+    fn parse_attachment(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut attachment = Attachment {
+            node: self.new_node(statement, ATTACHMENT_FLAG)?,
+            path: String::new(),
+            attachment_id: 0,
+            visibility: None,
+            unknown_tracks: None,
+        };
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut attachment.node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("AttachmentID") => attachment.attachment_id = s.number()?,
+                Some("Path") => attachment.path = s.string()?,
+                Some("Visibility") => attachment.visibility = Some(parse_transform(s)?),
+                _ => return Err(s.unknown("Attachment")),
+            }
+        }
+        self.model
+            .attachment_chunk
+            .get_or_insert_with(|| AttachmentChunk { data: Vec::new() })
+            .data
+            .push(attachment);
+        Ok(())
+    }
+
+    fn parse_pivot_points(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for s in statement.block()? {
+            let position = s.fixed_numbers::<f32>(3)?;
+            data.push(PivotPoint {
+                position: [position[0], position[1], position[2]],
+            });
+        }
+        self.model.pivot_point_chunk = Some(PivotPointChunk { data });
+        Ok(())
+    }
+
+    fn parse_particle_emitter(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut particle_emitter = ParticleEmitter {
+            node: self.new_node(statement, PARTICLE_EMITTER_FLAG)?,
+            emission_rate: 0.0,
+            gravity: 0.0,
+            longitude: 0.0,
+            latitude: 0.0,
+            path: String::new(),
+            life_span: 0.0,
+            speed: 0.0,
+            emission_rate_transform: None,
+            gravity_transform: None,
+            longitude_transform: None,
+            latitude_transform: None,
+            life_span_transform: None,
+            speed_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+        let emitter = &mut particle_emitter;
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut emitter.node, s, PARTICLE_EMITTER_FLAGS)? {
+                continue;
+            }
+            match s.keyword() {
+                Some("EmissionRate") => parse_property(
+                    s,
+                    &mut emitter.emission_rate,
+                    &mut emitter.emission_rate_transform,
+                )?,
+                Some("Gravity") => {
+                    parse_property(s, &mut emitter.gravity, &mut emitter.gravity_transform)?
+                }
+                Some("Longitude") => {
+                    parse_property(s, &mut emitter.longitude, &mut emitter.longitude_transform)?
+                }
+                Some("Latitude") => {
+                    parse_property(s, &mut emitter.latitude, &mut emitter.latitude_transform)?
+                }
+                Some("Visibility") => emitter.visibility_transform = Some(parse_transform(s)?),
+                Some("Particle") => {
+                    for p in s.block()? {
+                        match p.keyword() {
+                            Some("LifeSpan") => parse_property(
+                                p,
+                                &mut emitter.life_span,
+                                &mut emitter.life_span_transform,
+                            )?,
+                            Some("InitVelocity") => {
+                                parse_property(p, &mut emitter.speed, &mut emitter.speed_transform)?
+                            }
+                            Some("Path") => emitter.path = p.string()?,
+                            _ => return Err(p.unknown("Particle")),
+                        }
+                    }
+                }
+                _ => return Err(s.unknown("ParticleEmitter")),
+            }
+        }
+        self.model
+            .particle_emitter_chunk
+            .get_or_insert_with(|| ParticleEmitterChunk { data: Vec::new() })
+            .data
+            .push(particle_emitter);
+        Ok(())
+    }
+
+    fn parse_particle_emitter2(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let white = || Color {
+            b: 1.0,
+            g: 1.0,
+            r: 1.0,
+        };
+        let mut particle_emitter2 = ParticleEmitter2 {
+            node: self.new_node(statement, PARTICLE_EMITTER_FLAG)?,
+            speed: 0.0,
+            variation: 0.0,
+            latitude: 0.0,
+            gravity: 0.0,
+            life_span: 0.0,
+            emission_rate: 0.0,
+            width: 0.0,
+            length: 0.0,
+            filter_mode: 0,
+            rows: 1,
+            columns: 1,
+            head_or_tail: 0,
+            tail_length: 0.0,
+            time: 0.0,
+            segment_colors: [white(), white(), white()],
+            segment_alphas: [255, 255, 255],
+            segment_scaling: [1.0, 1.0, 1.0],
+            head_intervals: [0, 0, 1],
+            head_decay_intervals: [0, 0, 1],
+            tail_intervals: [0, 0, 1],
+            tail_decay_intervals: [0, 0, 1],
+            texture_id: 0,
+            squirt: 0,
+            priority_plane: 0,
+            replaceable_id: 0,
+            speed_transform: None,
+            variation_transform: None,
+            latitude_transform: None,
+            gravity_transform: None,
+            emission_rate_transform: None,
+            width_transform: None,
+            length_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+        let emitter = &mut particle_emitter2;
+        let intervals = |s: &Statement| -> Result<[u32; 3], MdlError> {
+            let v = s.fixed_numbers::<u32>(3)?;
+            Ok([v[0], v[1], v[2]])
+        };
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut emitter.node, s, PARTICLE_EMITTER2_FLAGS)?
+                || parse_enum(s, PARTICLE_EMITTER2_FILTER_MODES, &mut emitter.filter_mode)
+                || parse_enum(s, PARTICLE_EMITTER2_HEAD_OR_TAIL, &mut emitter.head_or_tail)
+            {
+                continue;
+            }
+            match s.keyword() {
+                Some("Speed") => {
+                    parse_property(s, &mut emitter.speed, &mut emitter.speed_transform)?
+                }
+                Some("Variation") => {
+                    parse_property(s, &mut emitter.variation, &mut emitter.variation_transform)?
+                }
+                Some("Latitude") => {
+                    parse_property(s, &mut emitter.latitude, &mut emitter.latitude_transform)?
+                }
+                Some("Gravity") => {
+                    parse_property(s, &mut emitter.gravity, &mut emitter.gravity_transform)?
+                }
+                Some("EmissionRate") => parse_property(
+                    s,
+                    &mut emitter.emission_rate,
+                    &mut emitter.emission_rate_transform,
+                )?,
+                Some("Width") => {
+                    parse_property(s, &mut emitter.width, &mut emitter.width_transform)?
+                }
+                Some("Length") => {
+                    parse_property(s, &mut emitter.length, &mut emitter.length_transform)?
+                }
+                Some("Visibility") => emitter.visibility_transform = Some(parse_transform(s)?),
+                Some("Squirt") => emitter.squirt = 1,
+                Some("LifeSpan") => emitter.life_span = s.number()?,
+                Some("Rows") => emitter.rows = s.number()?,
+                Some("Columns") => emitter.columns = s.number()?,
+                Some("TailLength") => emitter.tail_length = s.number()?,
+                Some("Time") => emitter.time = s.number()?,
+                Some("SegmentColor") => {
+                    let colors = s.block()?;
+                    if colors.len() != 3 {
+                        return Err(s.error("Expected 3 segment colors"));
+                    }
+                    for (color, c) in emitter.segment_colors.iter_mut().zip(colors) {
+                        if c.keyword() != Some("Color") {
+                            return Err(c.unknown("SegmentColor"));
+                        }
+                        *color = Color::from_statement(c)?;
+                    }
+                }
+                Some("Alpha") => {
+                    let v = s.fixed_numbers::<u8>(3)?;
+                    emitter.segment_alphas = [v[0], v[1], v[2]];
+                }
+                Some("ParticleScaling") => {
+                    let v = s.fixed_numbers::<f32>(3)?;
+                    emitter.segment_scaling = [v[0], v[1], v[2]];
+                }
+                Some("LifeSpanUVAnim") => emitter.head_intervals = intervals(s)?,
+                Some("DecayUVAnim") => emitter.head_decay_intervals = intervals(s)?,
+                Some("TailUVAnim") => emitter.tail_intervals = intervals(s)?,
+                Some("TailDecayUVAnim") => emitter.tail_decay_intervals = intervals(s)?,
+                Some("TextureID") => emitter.texture_id = s.number()?,
+                Some("ReplaceableId") => emitter.replaceable_id = s.number()?,
+                Some("PriorityPlane") => emitter.priority_plane = s.number()?,
+                _ => return Err(s.unknown("ParticleEmitter2")),
+            }
+        }
+        self.model
+            .particle_emitter2_chunk
+            .get_or_insert_with(|| ParticleEmitter2Chunk { data: Vec::new() })
+            .data
+            .push(particle_emitter2);
+        Ok(())
+    }
+
+    fn parse_popcorn_emitter(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut popcorn_emitter = PopcornEmitter {
+            node: self.new_node(statement, PARTICLE_EMITTER_FLAG)?,
+            life_span: 0.0,
+            emission_rate: 0.0,
+            speed: 0.0,
+            color: Color {
+                b: 1.0,
+                g: 1.0,
+                r: 1.0,
+            },
+            alpha: 1.0,
+            replaceable_id: 0,
+            path: String::new(),
+            animation_visibility_guide: String::new(),
+            alpha_transform: None,
+            color_transform: None,
+            emission_rate_transform: None,
+            life_span_transform: None,
+            speed_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+        let emitter = &mut popcorn_emitter;
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut emitter.node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("LifeSpan") => {
+                    parse_property(s, &mut emitter.life_span, &mut emitter.life_span_transform)?
+                }
+                Some("EmissionRate") => parse_property(
+                    s,
+                    &mut emitter.emission_rate,
+                    &mut emitter.emission_rate_transform,
+                )?,
+                Some("Speed") => {
+                    parse_property(s, &mut emitter.speed, &mut emitter.speed_transform)?
+                }
+                Some("Color") => {
+                    parse_property(s, &mut emitter.color, &mut emitter.color_transform)?
+                }
+                Some("Alpha") => {
+                    parse_property(s, &mut emitter.alpha, &mut emitter.alpha_transform)?
+                }
+                Some("Visibility") => emitter.visibility_transform = Some(parse_transform(s)?),
+                Some("ReplaceableId") => emitter.replaceable_id = s.number()?,
+                Some("Path") => emitter.path = s.string()?,
+                Some("AnimVisibilityGuide") => emitter.animation_visibility_guide = s.string()?,
+                _ => return Err(s.unknown("ParticleEmitterPopcorn")),
+            }
+        }
+        self.model
+            .popcorn_emitter_chunk
+            .get_or_insert_with(|| PopcornEmitterChunk { data: Vec::new() })
+            .data
+            .push(popcorn_emitter);
+        Ok(())
+    }
+
+    fn parse_ribbon_emitter(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut ribbon_emitter = RibbonEmitter {
+            node: self.new_node(statement, RIBBON_EMITTER_FLAG)?,
+            height_above: 0.0,
+            height_below: 0.0,
+            alpha: 1.0,
+            color: Color {
+                b: 1.0,
+                g: 1.0,
+                r: 1.0,
+            },
+            life_span: 0.0,
+            texture_slot: 0,
+            emission_rate: 0,
+            rows: 1,
+            columns: 1,
+            material_id: 0,
+            gravity: 0.0,
+            height_above_transform: None,
+            height_below_transform: None,
+            alpha_transform: None,
+            color_transform: None,
+            texture_slot_transform: None,
+            visibility_transform: None,
+            unknown_tracks: None,
+        };
+        let emitter = &mut ribbon_emitter;
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut emitter.node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("HeightAbove") => parse_property(
+                    s,
+                    &mut emitter.height_above,
+                    &mut emitter.height_above_transform,
+                )?,
+                Some("HeightBelow") => parse_property(
+                    s,
+                    &mut emitter.height_below,
+                    &mut emitter.height_below_transform,
+                )?,
+                Some("Alpha") => {
+                    parse_property(s, &mut emitter.alpha, &mut emitter.alpha_transform)?
+                }
+                Some("Color") => {
+                    parse_property(s, &mut emitter.color, &mut emitter.color_transform)?
+                }
+                Some("TextureSlot") => parse_property(
+                    s,
+                    &mut emitter.texture_slot,
+                    &mut emitter.texture_slot_transform,
+                )?,
+                Some("Visibility") => emitter.visibility_transform = Some(parse_transform(s)?),
+                Some("EmissionRate") => emitter.emission_rate = s.number()?,
+                Some("LifeSpan") => emitter.life_span = s.number()?,
+                Some("Gravity") => emitter.gravity = s.number()?,
+                Some("Rows") => emitter.rows = s.number()?,
+                Some("Columns") => emitter.columns = s.number()?,
+                Some("MaterialID") => emitter.material_id = s.number()?,
+                _ => return Err(s.unknown("RibbonEmitter")),
+            }
+        }
+        self.model
+            .ribbon_emitter_chunk
+            .get_or_insert_with(|| RibbonEmitterChunk { data: Vec::new() })
+            .data
+            .push(ribbon_emitter);
+        Ok(())
+    }
+
+    fn parse_event_object(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut event_object = EventObject {
+            node: self.new_node(statement, EVENT_OBJECT_FLAG)?,
+            global_sequence_id: None,
+            keys: None,
+        };
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut event_object.node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("EventTrack") => {
+                    let keys = event_object.keys.get_or_insert_with(Vec::new);
+                    for key in s.block()? {
+                        match key.keyword() {
+                            Some("GlobalSeqId") => {
+                                event_object.global_sequence_id =
+                                    Some(GlobalSequenceId(key.number()?))
+                            }
+                            Some(_) => return Err(key.unknown("EventTrack")),
+                            None => keys.push(key.number()?),
+                        }
+                    }
+                }
+                Some("GlobalSeqId") => {
+                    event_object.keys.get_or_insert_with(Vec::new);
+                    event_object.global_sequence_id = Some(GlobalSequenceId(s.number()?))
+                }
+                _ => return Err(s.unknown("EventObject")),
+            }
+        }
+        self.model
+            .event_object_chunk
+            .get_or_insert_with(|| EventObjectChunk { data: Vec::new() })
+            .data
+            .push(event_object);
+        Ok(())
+    }
+
+    fn parse_camera(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut camera = Camera {
+            name: statement.string()?,
+            position: [0.0; 3],
+            field_of_view: 0.0,
+            far_clipping_plane: 0.0,
+            near_clipping_plane: 0.0,
+            target_position: [0.0; 3],
+            translation: None,
+            rotation: None,
+            target_translation: None,
+            unknown_tracks: None,
+        };
+        for s in statement.block()? {
+            match s.keyword() {
+                Some("Position") => {
+                    let v = s.fixed_numbers::<f32>(3)?;
+                    camera.position = [v[0], v[1], v[2]];
+                }
+                Some("Translation") => camera.translation = Some(parse_transform(s)?),
+                Some("Rotation") => camera.rotation = Some(parse_transform(s)?),
+                Some("FieldOfView") => camera.field_of_view = s.number()?,
+                Some("FarClip") => camera.far_clipping_plane = s.number()?,
+                Some("NearClip") => camera.near_clipping_plane = s.number()?,
+                Some("Target") => {
+                    for t in s.block()? {
+                        match t.keyword() {
+                            Some("Position") => {
+                                let v = t.fixed_numbers::<f32>(3)?;
+                                camera.target_position = [v[0], v[1], v[2]];
+                            }
+                            Some("Translation") => {
+                                camera.target_translation = Some(parse_transform(t)?)
+                            }
+                            _ => return Err(t.unknown("Target")),
+                        }
+                    }
+                }
+                _ => return Err(s.unknown("Camera")),
+            }
+        }
+        self.model
+            .camera_chunk
+            .get_or_insert_with(|| CameraChunk { data: Vec::new() })
+            .data
+            .push(camera);
+        Ok(())
+    }
+
+    fn parse_face_effect(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut face_effect = FaceEffect {
+            target: statement.string()?,
+            path: String::new(),
+        };
+        for s in statement.block()? {
+            match s.keyword() {
+                Some("Path") => face_effect.path = s.string()?,
+                _ => return Err(s.unknown("FaceFX")),
+            }
+        }
+        self.model
+            .face_effect_chunk
+            .get_or_insert_with(|| FaceEffectChunk { data: Vec::new() })
+            .data
+            .push(face_effect);
+        Ok(())
+    }
+
+    fn parse_bind_pose(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut data = Vec::new();
+        for matrices in statement.block()? {
+            if matrices.keyword() != Some("Matrices") {
+                return Err(matrices.unknown("BindPose"));
+            }
+            for s in matrices.block()? {
+                let values = s.fixed_numbers::<f32>(12)?;
+                let mut matrix = [0f32; 12];
+                matrix.copy_from_slice(&values);
+                data.push(BindMatrix { matrix });
+            }
+        }
+        self.model.bind_pose_chunk = Some(BindPoseChunk { data });
+        Ok(())
+    }
+
+    fn parse_collision_shape(&mut self, statement: &Statement) -> Result<(), MdlError> {
+        let mut node = self.new_node(statement, COLLISION_SHAPE_FLAG)?;
+        let mut shape_type = None;
+        let mut vertices = Vec::new();
+        let mut radius = 0.0;
+        for s in statement.block()? {
+            if Self::parse_node_property(&mut node, s, &[])? {
+                continue;
+            }
+            match s.keyword() {
+                Some("Box") => shape_type = Some(0),
+                Some("Plane") => shape_type = Some(1),
+                Some("Sphere") => shape_type = Some(2),
+                Some("Cylinder") => shape_type = Some(3),
+                Some("Vertices") => {
+                    for v in s.block()? {
+                        vertices.push(Vec3::from_statement(v)?);
+                    }
+                }
+                Some("BoundsRadius") => radius = s.number()?,
+                _ => return Err(s.unknown("CollisionShape")),
+            }
+        }
+
+        let expected_vertices = if shape_type == Some(2) { 1 } else { 2 };
+        if vertices.len() != expected_vertices {
+            return Err(statement.error(format!(
+                "Expected {} vertices - Found {}",
+                expected_vertices,
+                vertices.len()
+            )));
+        }
+        let mut vertices = vertices.into_iter();
+        let mut vertex = || vertices.next().unwrap();
+        let shape = match shape_type {
+            Some(0) => Shape::Box {
+                minimum: vertex(),
+                maximum: vertex(),
+            },
+            Some(1) => Shape::Plane {
+                minimum: vertex(),
+                maximum: vertex(),
+            },
+            Some(2) => Shape::Sphere {
+                center: vertex(),
+                radius,
+            },
+            Some(_) => Shape::Cylinder {
+                base: vertex(),
+                top: vertex(),
+                radius,
+            },
+            None => return Err(statement.error("Missing collision shape type")),
+        };
+
+        self.model
+            .collision_shape_chunk
+            .get_or_insert_with(|| CollisionShapeChunk { data: Vec::new() })
+            .data
+            .push(CollisionShape { node, shape });
+        Ok(())
+    }
+}
+
+pub fn read_mdl(text: &str) -> Result<MDLXModel, MdlError> {
+    let tokens = Tokenizer::new(text).tokenize()?;
+    let statements = parse_statements(&tokens, &mut 0, None)?;
+
+    let mut parser = ModelParser {
+        model: MDLXModel::default(),
+        node_count: 0,
+    };
+    parser.parse(&statements)?;
+
+    // Fill in version dependent fields like an MDX reader would see them
+    let mut model = parser.model;
+    model.match_version().map_err(|error| {
+        match statements.iter().find(|s| s.keyword() == Some("Version")) {
+            Some(version) => version.error(error.to_string()),
+            None => MdlError::new(1, 1, error.to_string()),
+        }
+    })?;
+    Ok(model)
+}
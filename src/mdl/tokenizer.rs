@@ -0,0 +1,157 @@
+use crate::mdl::MdlError;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum TokenKind {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Colon,
+    Comma,
+    OpenBrace,
+    CloseBrace,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+pub struct Tokenizer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Tokenizer {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn tokenize(mut self) -> Result<Vec<Token>, MdlError> {
+        let mut tokens = Vec::new();
+        while let Some(token) = self.next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '/' {
+                // Comments run until the end of line
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                if lookahead.peek() != Some(&'/') {
+                    return;
+                }
+                while let Some(&c) = self.chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.bump();
+                }
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, MdlError> {
+        self.skip_whitespace_and_comments();
+
+        let (line, column) = (self.line, self.column);
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok(None),
+        };
+
+        let kind = match c {
+            '{' => {
+                self.bump();
+                TokenKind::OpenBrace
+            }
+            '}' => {
+                self.bump();
+                TokenKind::CloseBrace
+            }
+            ',' => {
+                self.bump();
+                TokenKind::Comma
+            }
+            ':' => {
+                self.bump();
+                TokenKind::Colon
+            }
+            '"' => {
+                self.bump();
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        // Only quotes and backslashes are escaped, other backslashes belong to paths
+                        Some('\\') if matches!(self.chars.peek(), Some('"') | Some('\\')) => {
+                            value.extend(self.bump());
+                        }
+                        Some(c) => value.push(c),
+                        None => {
+                            return Err(MdlError::new(line, column, "Unterminated string"));
+                        }
+                    }
+                }
+                TokenKind::Str(value)
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let mut value = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '+' || c == '.' {
+                        value.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Number(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut value = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' {
+                        value.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Ident(value)
+            }
+            c => {
+                return Err(MdlError::new(
+                    line,
+                    column,
+                    format!("Unexpected character '{}'", c),
+                ));
+            }
+        };
+
+        Ok(Some(Token { kind, line, column }))
+    }
+}
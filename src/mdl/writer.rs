@@ -0,0 +1,956 @@
+use crate::chunks::*;
+use crate::mdl::*;
+use crate::{FourCC, MDLXModel};
+use std::cell::Cell;
+
+// Quotes and backslashes inside strings are escaped, so a path may end with a backslash
+fn quoted(text: &str) -> String {
+    let text = text.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", text)
+}
+
+trait MdlValue {
+    fn to_mdl(&self) -> String;
+
+    // NaN and infinity have no spelling in MDL
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+impl MdlValue for f32 {
+    fn to_mdl(&self) -> String {
+        format!("{}", self)
+    }
+
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl MdlValue for u32 {
+    fn to_mdl(&self) -> String {
+        format!("{}", self)
+    }
+}
+
+impl MdlValue for Vec3 {
+    fn to_mdl(&self) -> String {
+        format!("{{ {}, {}, {} }}", self.x, self.y, self.z)
+    }
+
+    fn is_finite(&self) -> bool {
+        [self.x, self.y, self.z].is_finite()
+    }
+}
+
+impl MdlValue for Vec4 {
+    fn to_mdl(&self) -> String {
+        format!("{{ {}, {}, {}, {} }}", self.x, self.y, self.z, self.w)
+    }
+
+    fn is_finite(&self) -> bool {
+        [self.x, self.y, self.z, self.w].is_finite()
+    }
+}
+
+impl MdlValue for Color {
+    fn to_mdl(&self) -> String {
+        format!("{{ {}, {}, {} }}", self.b, self.g, self.r)
+    }
+
+    fn is_finite(&self) -> bool {
+        [self.b, self.g, self.r].is_finite()
+    }
+}
+
+impl MdlValue for [f32; 3] {
+    fn to_mdl(&self) -> String {
+        format!("{{ {}, {}, {} }}", self[0], self[1], self[2])
+    }
+
+    fn is_finite(&self) -> bool {
+        self[..].is_finite()
+    }
+}
+
+impl MdlValue for [f32] {
+    fn to_mdl(&self) -> String {
+        list(self)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.iter().all(|value| value.is_finite())
+    }
+}
+
+fn list<T: std::fmt::Display>(values: &[T]) -> String {
+    let values = values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>();
+    format!("{{ {} }}", values.join(", "))
+}
+
+struct MdlWriter {
+    text: String,
+    indent: usize,
+    // First thing met that MDL has no way to express
+    error: Option<MdlError>,
+    // Set when a value of the line being built isn't finite
+    non_finite: Cell<bool>,
+}
+
+impl MdlWriter {
+    fn unsupported<S: Into<String>>(&mut self, message: S) {
+        if self.error.is_none() {
+            let line = self.text.lines().count() + 1;
+            self.error = Some(MdlError::new(line, 1, message));
+        }
+    }
+
+    fn unknown_tracks(&mut self, unknown_tracks: &Option<UnknownTracks>) {
+        if let Some(unknown_tracks) = unknown_tracks {
+            self.unsupported(format!(
+                "Unknown track {} can't be written to MDL",
+                FourCC(unknown_tracks.tag)
+            ));
+        }
+    }
+
+    fn value<T: MdlValue + ?Sized>(&self, value: &T) -> String {
+        if !value.is_finite() {
+            self.non_finite.set(true);
+        }
+        value.to_mdl()
+    }
+
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        if self.non_finite.take() {
+            self.unsupported("NaN and infinite values can't be written to MDL");
+        }
+        for _ in 0..self.indent {
+            self.text.push('\t');
+        }
+        self.text.push_str(line.as_ref());
+        self.text.push('\n');
+    }
+
+    fn open<S: AsRef<str>>(&mut self, header: S) {
+        self.line(format!("{} {{", header.as_ref()));
+        self.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn flags(&mut self, names: &[(&str, u32)], flags: u32) {
+        for (name, flag) in names {
+            if flags & flag != 0 {
+                self.line(format!("{},", name));
+            }
+        }
+    }
+
+    fn name(&mut self, names: &[&str], value: u32) {
+        match names.get(value as usize) {
+            Some(name) => self.line(format!("{},", name)),
+            None => self.unsupported(format!("Unknown value {} can't be written to MDL", value)),
+        }
+    }
+
+    fn extent(&mut self, extent: &Extent) {
+        self.line(format!("MinimumExtent {},", self.value(&extent.minimum)));
+        self.line(format!("MaximumExtent {},", self.value(&extent.maximum)));
+        self.line(format!(
+            "BoundsRadius {},",
+            self.value(&extent.bounds_radius)
+        ));
+    }
+
+    fn transform<T: MdlValue>(&mut self, name: &str, transform: &Transform<T>) {
+        self.open(format!("{} {}", name, transform.tracks.len()));
+        self.line(match transform.interpolation() {
+            Interpolation::DontInterp => "DontInterp,",
+            Interpolation::Linear => "Linear,",
+            Interpolation::Hermite => "Hermite,",
+            Interpolation::Bezier => "Bezier,",
+        });
+        if let Some(global_sequence_id) = transform.global_sequence_id {
+            self.line(format!("GlobalSeqId {},", global_sequence_id.0));
+        }
+        match &transform.tracks {
+            Tracks::DontInterp(tracks) | Tracks::Linear(tracks) => {
+                for track in tracks {
+                    self.line(format!("{}: {},", track.time, self.value(&track.value)));
+                }
+            }
+            Tracks::Hermite(tracks) | Tracks::Bezier(tracks) => {
+                for track in tracks {
+                    self.line(format!("{}: {},", track.time, self.value(&track.value)));
+                    self.indent += 1;
+                    self.line(format!("InTan {},", self.value(&track.in_tan)));
+                    self.line(format!("OutTan {},", self.value(&track.out_tan)));
+                    self.indent -= 1;
+                }
+            }
+        }
+        self.close();
+    }
+
+    fn animated<T: MdlValue>(&mut self, name: &str, transform: &Option<Transform<T>>) {
+        if let Some(transform) = transform {
+            self.transform(name, transform);
+        }
+    }
+
+    // Either the track or the static value, the way other tools expect it
+    fn property<T: MdlValue>(&mut self, name: &str, value: &T, transform: &Option<Transform<T>>) {
+        match transform {
+            Some(transform) => self.transform(name, transform),
+            None => self.line(format!("static {} {},", name, self.value(value))),
+        }
+    }
+
+    fn node(&mut self, node: &Node, flags: &[(&str, u32)]) {
+        self.line(format!("ObjectId {},", node.object_id));
+        if node.parent_id != 0xFFFF_FFFF {
+            self.line(format!("Parent {},", node.parent_id));
+        }
+        if node.flags & 0x7 != 0 {
+            self.open("DontInherit");
+            self.flags(DONT_INHERIT_FLAGS, node.flags);
+            self.close();
+        }
+        self.flags(NODE_FLAGS, node.flags);
+        self.flags(flags, node.flags);
+        self.animated("Translation", &node.translation);
+        self.animated("Rotation", &node.rotation);
+        self.animated("Scaling", &node.scaling);
+        self.unknown_tracks(&node.unknown_tracks);
+    }
+
+    fn write(&mut self, model: &MDLXModel) {
+        if let Some(version) = &model.version_chunk {
+            self.open("Version");
+            self.line(format!("FormatVersion {},", version.version));
+            self.close();
+        }
+        if let Some(model_chunk) = &model.model_chunk {
+            self.write_model(model_chunk, model);
+        }
+        if let Some(sequences) = &model.sequence_chunk {
+            self.write_sequences(sequences);
+        }
+        if let Some(global_sequences) = &model.global_sequence_chunk {
+            self.open(format!("GlobalSequences {}", global_sequences.data.len()));
+            for global_sequence in &global_sequences.data {
+                self.line(format!("Duration {},", global_sequence.duration));
+            }
+            self.close();
+        }
+        if let Some(textures) = &model.texture_chunk {
+            self.write_textures(textures);
+        }
+        if let Some(materials) = &model.material_chunk {
+            self.write_materials(materials);
+        }
+        if let Some(texture_animations) = &model.texture_animation_chunk {
+            self.open(format!("TextureAnims {}", texture_animations.data.len()));
+            for texture_animation in &texture_animations.data {
+                self.open("TVertexAnim");
+                self.animated("Translation", &texture_animation.translation);
+                self.animated("Rotation", &texture_animation.rotation);
+                self.animated("Scaling", &texture_animation.scaling);
+                self.unknown_tracks(&texture_animation.unknown_tracks);
+                self.close();
+            }
+            self.close();
+        }
+        for geoset in model.geoset_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.write_geoset(geoset);
+        }
+        for geoset_animation in model
+            .geoset_animation_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_geoset_animation(geoset_animation);
+        }
+        for bone in model.bone_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.write_bone(bone);
+        }
+        for light in model.light_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.write_light(light);
+        }
+        for helper in model.helper_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.open(format!("Helper {}", quoted(&helper.node.name)));
+            self.node(&helper.node, &[]);
+            self.close();
+        }
+        for attachment in model.attachment_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.write_attachment(attachment);
+        }
+        if let Some(pivot_points) = &model.pivot_point_chunk {
+            self.open(format!("PivotPoints {}", pivot_points.data.len()));
+            for pivot_point in &pivot_points.data {
+                self.line(format!("{},", self.value(&pivot_point.position)));
+            }
+            self.close();
+        }
+        for particle_emitter in model
+            .particle_emitter_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_particle_emitter(particle_emitter);
+        }
+        for particle_emitter2 in model
+            .particle_emitter2_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_particle_emitter2(particle_emitter2);
+        }
+        for popcorn_emitter in model
+            .popcorn_emitter_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_popcorn_emitter(popcorn_emitter);
+        }
+        for ribbon_emitter in model
+            .ribbon_emitter_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_ribbon_emitter(ribbon_emitter);
+        }
+        for event_object in model
+            .event_object_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_event_object(event_object);
+        }
+        for camera in model.camera_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.write_camera(camera);
+        }
+        for collision_shape in model
+            .collision_shape_chunk
+            .iter()
+            .flat_map(|chunk| &chunk.data)
+        {
+            self.write_collision_shape(collision_shape);
+        }
+        for face_effect in model.face_effect_chunk.iter().flat_map(|chunk| &chunk.data) {
+            self.open(format!("FaceFX {}", quoted(&face_effect.target)));
+            self.line(format!("Path {},", quoted(&face_effect.path)));
+            self.close();
+        }
+        if let Some(bind_pose) = &model.bind_pose_chunk {
+            self.open("BindPose");
+            self.open(format!("Matrices {}", bind_pose.data.len()));
+            for bind_matrix in &bind_pose.data {
+                self.line(format!("{},", self.value(&bind_matrix.matrix[..])));
+            }
+            self.close();
+            self.close();
+        }
+        if let Some(unknown_chunk) = model.unknown_chunks.first() {
+            self.unsupported(format!(
+                "Unknown chunk {} can't be written to MDL",
+                FourCC(unknown_chunk.tag)
+            ));
+        }
+    }
+
+    fn write_model(&mut self, model_chunk: &ModelChunk, model: &MDLXModel) {
+        self.open(format!("Model {}", quoted(&model_chunk.name)));
+        let counts = [
+            (
+                "NumGeosets",
+                model.geoset_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumGeosetAnims",
+                model.geoset_animation_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumLights",
+                model.light_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumHelpers",
+                model.helper_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            ("NumBones", model.bone_chunk.as_ref().map(|c| c.data.len())),
+            (
+                "NumAttachments",
+                model.attachment_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumParticleEmitters",
+                model.particle_emitter_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumParticleEmitters2",
+                model.particle_emitter2_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumRibbonEmitters",
+                model.ribbon_emitter_chunk.as_ref().map(|c| c.data.len()),
+            ),
+            (
+                "NumEvents",
+                model.event_object_chunk.as_ref().map(|c| c.data.len()),
+            ),
+        ];
+        for (name, count) in counts.iter() {
+            if let Some(count) = count {
+                self.line(format!("{} {},", name, count));
+            }
+        }
+        self.line(format!("BlendTime {},", model_chunk.blend_time));
+        self.extent(&model_chunk.extent);
+        self.close();
+    }
+
+    fn write_sequences(&mut self, sequences: &SequenceChunk) {
+        self.open(format!("Sequences {}", sequences.data.len()));
+        for sequence in &sequences.data {
+            self.open(format!("Anim {}", quoted(&sequence.name)));
+            self.line(format!(
+                "Interval {{ {}, {} }},",
+                sequence.interval_start, sequence.interval_end
+            ));
+            if sequence.non_looping != 0 {
+                self.line("NonLooping,");
+            }
+            if sequence.move_speed != 0.0 {
+                self.line(format!("MoveSpeed {},", self.value(&sequence.move_speed)));
+            }
+            if sequence.rarity != 0.0 {
+                self.line(format!("Rarity {},", self.value(&sequence.rarity)));
+            }
+            self.extent(&sequence.extent);
+            self.close();
+        }
+        self.close();
+    }
+
+    fn write_textures(&mut self, textures: &TextureChunk) {
+        self.open(format!("Textures {}", textures.data.len()));
+        for texture in &textures.data {
+            self.open("Bitmap");
+            self.line(format!("Image {},", quoted(&texture.file_name)));
+            if texture.replaceable_id != 0 {
+                self.line(format!("ReplaceableId {},", texture.replaceable_id));
+            }
+            self.flags(TEXTURE_FLAGS, texture.flags);
+            self.close();
+        }
+        self.close();
+    }
+
+    fn write_materials(&mut self, materials: &MaterialChunk) {
+        self.open(format!("Materials {}", materials.data.len()));
+        for material in &materials.data {
+            self.open("Material");
+            self.flags(MATERIAL_FLAGS, material.flags);
+            if material.priority_plane != 0 {
+                self.line(format!("PriorityPlane {},", material.priority_plane));
+            }
+            if let Some(shader) = material.shader.as_ref() {
+                self.line(format!("Shader {},", quoted(shader)));
+            }
+            for layer in &material.layers {
+                self.open("Layer");
+                match LAYER_FILTER_MODES.get(layer.filter_mode as usize) {
+                    Some(filter_mode) => self.line(format!("FilterMode {},", filter_mode)),
+                    None => self.unsupported(format!(
+                        "Unknown filter mode {} can't be written to MDL",
+                        layer.filter_mode
+                    )),
+                }
+                self.flags(LAYER_SHADING_FLAGS, layer.shading_flags);
+                self.property("TextureID", &layer.texture_id, &layer.texture_id_transform);
+                if layer.texture_animation_id != 0xFFFF_FFFF {
+                    self.line(format!("TVertexAnimId {},", layer.texture_animation_id));
+                }
+                self.line(format!("CoordId {},", layer.coord_id));
+                self.property("Alpha", &layer.alpha, &layer.alpha_transform);
+                match layer.hd.as_ref() {
+                    Some(hd) => {
+                        self.property(
+                            "EmissiveGain",
+                            &hd.emissive_gain,
+                            &layer.emissive_gain_transform,
+                        );
+                        self.property(
+                            "FresnelColor",
+                            &hd.fresnel_color,
+                            &layer.fresnel_color_transform,
+                        );
+                        self.property(
+                            "FresnelOpacity",
+                            &hd.fresnel_opacity,
+                            &layer.fresnel_alpha_transform,
+                        );
+                        self.property(
+                            "FresnelTeamColor",
+                            &hd.fresnel_team_color,
+                            &layer.fresnel_team_color_transform,
+                        );
+                    }
+                    None => {
+                        self.animated("EmissiveGain", &layer.emissive_gain_transform);
+                        self.animated("FresnelColor", &layer.fresnel_color_transform);
+                        self.animated("FresnelOpacity", &layer.fresnel_alpha_transform);
+                        self.animated("FresnelTeamColor", &layer.fresnel_team_color_transform);
+                    }
+                }
+                if layer.unknown_bytes.is_some() {
+                    self.unsupported("Layers of version 1100 can't be written to MDL");
+                }
+                self.unknown_tracks(&layer.unknown_tracks);
+                self.close();
+            }
+            self.close();
+        }
+        self.close();
+    }
+
+    fn write_geoset(&mut self, geoset: &Geoset) {
+        self.open("Geoset");
+
+        self.open(format!("Vertices {}", geoset.vertex_positions.len()));
+        for vertex in &geoset.vertex_positions {
+            self.line(format!("{},", self.value(&vertex.position)));
+        }
+        self.close();
+
+        self.open(format!("Normals {}", geoset.vertex_normals.len()));
+        for normal in &geoset.vertex_normals {
+            self.line(format!("{},", self.value(&normal.normal)));
+        }
+        self.close();
+
+        for texture_coordinate_set in &geoset.texture_coordinate_sets {
+            self.open(format!(
+                "TVertices {}",
+                texture_coordinate_set.texture_coordinates.len()
+            ));
+            for uv in &texture_coordinate_set.texture_coordinates {
+                self.line(format!("{},", self.value(&uv[..])));
+            }
+            self.close();
+        }
+
+        if let Some(tangents) = geoset.tangents.as_ref() {
+            self.open(format!("Tangents {}", tangents.len()));
+            for tangent in tangents {
+                self.line(format!("{},", self.value(tangent)));
+            }
+            self.close();
+        }
+
+        // Four bone indexes and four weights per vertex
+        if let Some(skin) = geoset.skin.as_ref() {
+            self.open(format!("SkinWeights {}", skin.len() / 8));
+            for weights in skin.chunks(8) {
+                let values = weights
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<String>>();
+                self.line(format!("{},", values.join(", ")));
+            }
+            self.close();
+        }
+
+        self.open("VertexGroup");
+        for vertex_group in &geoset.vertex_groups {
+            self.line(format!("{},", vertex_group.matrix_group));
+        }
+        self.close();
+
+        let indexes = geoset
+            .faces
+            .iter()
+            .flat_map(|face| vec![face.index1, face.index2, face.index3])
+            .collect::<Vec<u16>>();
+        self.open(format!(
+            "Faces {} {}",
+            geoset.face_groups.len(),
+            indexes.len()
+        ));
+        let mut start = 0usize;
+        for (face_type_group, face_group) in geoset.face_type_groups.iter().zip(&geoset.face_groups)
+        {
+            let face_type = FACE_TYPES
+                .get(face_type_group.face_type as usize)
+                .unwrap_or(&"Triangles");
+            let end = (start + face_group.number_of_indexes as usize).min(indexes.len());
+            self.open(face_type);
+            self.line(format!("{},", list(&indexes[start..end])));
+            self.close();
+            start = end;
+        }
+        self.close();
+
+        self.open(format!(
+            "Groups {} {}",
+            geoset.matrix_groups.len(),
+            geoset.matrix_indexes.len()
+        ));
+        let matrix_indexes = geoset
+            .matrix_indexes
+            .iter()
+            .map(|matrix_index| matrix_index.matrix_index)
+            .collect::<Vec<u32>>();
+        let mut start = 0usize;
+        for matrix_group in &geoset.matrix_groups {
+            let end = (start + matrix_group.matrix_group_size as usize).min(matrix_indexes.len());
+            self.line(format!("Matrices {},", list(&matrix_indexes[start..end])));
+            start = end;
+        }
+        self.close();
+
+        self.extent(&geoset.extent);
+        for extent in &geoset.extent_sequences {
+            self.open("Anim");
+            self.extent(extent);
+            self.close();
+        }
+
+        self.line(format!("MaterialID {},", geoset.material_id));
+        self.line(format!("SelectionGroup {},", geoset.selection_group));
+        if geoset.selection_type == 4 {
+            self.line("Unselectable,");
+        }
+        if let Some(lod) = geoset.lod.as_ref() {
+            self.line(format!("LevelOfDetail {},", lod.lod));
+            self.line(format!("Name {},", quoted(&lod.lod_name)));
+        }
+        self.close();
+    }
+
+    fn write_geoset_animation(&mut self, geoset_animation: &GeosetAnimation) {
+        self.open("GeosetAnim");
+        if geoset_animation.flags & 0x1 != 0 {
+            self.line("DropShadow,");
+        }
+        self.property(
+            "Alpha",
+            &geoset_animation.alpha,
+            &geoset_animation.geoset_alpha,
+        );
+        // Reading sets the color flag for any Color, so it can't be written without it
+        if geoset_animation.flags & !0x3 != 0 {
+            self.unsupported(format!(
+                "Geoset animation flags {:#x} can't be written to MDL",
+                geoset_animation.flags
+            ));
+        } else if geoset_animation.flags & 0x2 != 0 {
+            match &geoset_animation.geoset_color {
+                Some(geoset_color) => self.transform("Color", geoset_color),
+                None => self.line(format!(
+                    "static Color {},",
+                    self.value(&geoset_animation.color)
+                )),
+            }
+        } else if geoset_animation.geoset_color.is_some() {
+            self.unsupported(
+                "Animated geoset color without the color flag can't be written to MDL",
+            );
+        }
+        self.unknown_tracks(&geoset_animation.unknown_tracks);
+        self.line(format!("GeosetId {},", geoset_animation.geoset_id));
+        self.close();
+    }
+
+    fn write_bone(&mut self, bone: &Bone) {
+        self.open(format!("Bone {}", quoted(&bone.node.name)));
+        self.node(&bone.node, &[]);
+        if bone.geoset_id == 0xFFFF_FFFF {
+            self.line("GeosetId Multiple,");
+        } else {
+            self.line(format!("GeosetId {},", bone.geoset_id));
+        }
+        if bone.geoset_animation_id == 0xFFFF_FFFF {
+            self.line("GeosetAnimId None,");
+        } else {
+            self.line(format!("GeosetAnimId {},", bone.geoset_animation_id));
+        }
+        self.close();
+    }
+
+    fn write_light(&mut self, light: &Light) {
+        self.open(format!("Light {}", quoted(&light.node.name)));
+        self.node(&light.node, &[]);
+        self.name(LIGHT_TYPES, light.light_type);
+        self.line(format!(
+            "static AttenuationStart {},",
+            self.value(&light.attenuation_start)
+        ));
+        self.animated("AttenuationStart", &light.attenuation_start_transform);
+        self.line(format!(
+            "static AttenuationEnd {},",
+            self.value(&light.attenuation_end)
+        ));
+        self.animated("AttenuationEnd", &light.attenuation_end_transform);
+        self.property("Intensity", &light.intensity, &light.intensity_transform);
+        self.property("Color", &light.color, &light.color_transform);
+        self.property(
+            "AmbIntensity",
+            &light.ambient_intensity,
+            &light.ambient_intensity_transform,
+        );
+        self.property(
+            "AmbColor",
+            &light.ambient_color,
+            &light.ambient_color_transform,
+        );
+        self.animated("Visibility", &light.visibility_transform);
+        self.unknown_tracks(&light.unknown_tracks);
+        self.close();
+    }
+
+    fn write_attachment(&mut self, attachment: &Attachment) {
+        self.open(format!("Attachment {}", quoted(&attachment.node.name)));
+        self.node(&attachment.node, &[]);
+        self.line(format!("AttachmentID {},", attachment.attachment_id));
+        if !attachment.path.is_empty() {
+            self.line(format!("Path {},", quoted(&attachment.path)));
+        }
+        self.animated("Visibility", &attachment.visibility);
+        self.unknown_tracks(&attachment.unknown_tracks);
+        self.close();
+    }
+
+    fn write_particle_emitter(&mut self, emitter: &ParticleEmitter) {
+        self.open(format!("ParticleEmitter {}", quoted(&emitter.node.name)));
+        self.node(&emitter.node, PARTICLE_EMITTER_FLAGS);
+        self.property(
+            "EmissionRate",
+            &emitter.emission_rate,
+            &emitter.emission_rate_transform,
+        );
+        self.property("Gravity", &emitter.gravity, &emitter.gravity_transform);
+        self.property(
+            "Longitude",
+            &emitter.longitude,
+            &emitter.longitude_transform,
+        );
+        self.property("Latitude", &emitter.latitude, &emitter.latitude_transform);
+        self.animated("Visibility", &emitter.visibility_transform);
+        self.open("Particle");
+        self.property("LifeSpan", &emitter.life_span, &emitter.life_span_transform);
+        self.property("InitVelocity", &emitter.speed, &emitter.speed_transform);
+        self.line(format!("Path {},", quoted(&emitter.path)));
+        self.close();
+        self.unknown_tracks(&emitter.unknown_tracks);
+        self.close();
+    }
+
+    fn write_particle_emitter2(&mut self, emitter: &ParticleEmitter2) {
+        self.open(format!("ParticleEmitter2 {}", quoted(&emitter.node.name)));
+        self.node(&emitter.node, PARTICLE_EMITTER2_FLAGS);
+        self.property("Speed", &emitter.speed, &emitter.speed_transform);
+        self.property(
+            "Variation",
+            &emitter.variation,
+            &emitter.variation_transform,
+        );
+        self.property("Latitude", &emitter.latitude, &emitter.latitude_transform);
+        self.property("Gravity", &emitter.gravity, &emitter.gravity_transform);
+        self.animated("Visibility", &emitter.visibility_transform);
+        if emitter.squirt != 0 {
+            self.line("Squirt,");
+        }
+        self.line(format!("LifeSpan {},", self.value(&emitter.life_span)));
+        self.property(
+            "EmissionRate",
+            &emitter.emission_rate,
+            &emitter.emission_rate_transform,
+        );
+        self.property("Width", &emitter.width, &emitter.width_transform);
+        self.property("Length", &emitter.length, &emitter.length_transform);
+        self.name(PARTICLE_EMITTER2_FILTER_MODES, emitter.filter_mode);
+        self.line(format!("Rows {},", emitter.rows));
+        self.line(format!("Columns {},", emitter.columns));
+        self.name(PARTICLE_EMITTER2_HEAD_OR_TAIL, emitter.head_or_tail);
+        self.line(format!("TailLength {},", self.value(&emitter.tail_length)));
+        self.line(format!("Time {},", self.value(&emitter.time)));
+        self.open("SegmentColor");
+        for color in &emitter.segment_colors {
+            self.line(format!("Color {},", self.value(color)));
+        }
+        self.close();
+        self.line(format!("Alpha {},", list(&emitter.segment_alphas)));
+        self.line(format!(
+            "ParticleScaling {},",
+            self.value(&emitter.segment_scaling)
+        ));
+        self.line(format!("LifeSpanUVAnim {},", list(&emitter.head_intervals)));
+        self.line(format!(
+            "DecayUVAnim {},",
+            list(&emitter.head_decay_intervals)
+        ));
+        self.line(format!("TailUVAnim {},", list(&emitter.tail_intervals)));
+        self.line(format!(
+            "TailDecayUVAnim {},",
+            list(&emitter.tail_decay_intervals)
+        ));
+        self.line(format!("TextureID {},", emitter.texture_id));
+        if emitter.replaceable_id != 0 {
+            self.line(format!("ReplaceableId {},", emitter.replaceable_id));
+        }
+        if emitter.priority_plane != 0 {
+            self.line(format!("PriorityPlane {},", emitter.priority_plane));
+        }
+        self.unknown_tracks(&emitter.unknown_tracks);
+        self.close();
+    }
+
+    fn write_popcorn_emitter(&mut self, emitter: &PopcornEmitter) {
+        self.open(format!(
+            "ParticleEmitterPopcorn {}",
+            quoted(&emitter.node.name)
+        ));
+        self.node(&emitter.node, &[]);
+        self.property("LifeSpan", &emitter.life_span, &emitter.life_span_transform);
+        self.property(
+            "EmissionRate",
+            &emitter.emission_rate,
+            &emitter.emission_rate_transform,
+        );
+        self.property("Speed", &emitter.speed, &emitter.speed_transform);
+        self.property("Color", &emitter.color, &emitter.color_transform);
+        self.property("Alpha", &emitter.alpha, &emitter.alpha_transform);
+        self.animated("Visibility", &emitter.visibility_transform);
+        if emitter.replaceable_id != 0 {
+            self.line(format!("ReplaceableId {},", emitter.replaceable_id));
+        }
+        self.line(format!("Path {},", quoted(&emitter.path)));
+        self.line(format!(
+            "AnimVisibilityGuide {},",
+            quoted(&emitter.animation_visibility_guide)
+        ));
+        self.unknown_tracks(&emitter.unknown_tracks);
+        self.close();
+    }
+
+    fn write_ribbon_emitter(&mut self, emitter: &RibbonEmitter) {
+        self.open(format!("RibbonEmitter {}", quoted(&emitter.node.name)));
+        self.node(&emitter.node, &[]);
+        self.property(
+            "HeightAbove",
+            &emitter.height_above,
+            &emitter.height_above_transform,
+        );
+        self.property(
+            "HeightBelow",
+            &emitter.height_below,
+            &emitter.height_below_transform,
+        );
+        self.property("Alpha", &emitter.alpha, &emitter.alpha_transform);
+        self.property("Color", &emitter.color, &emitter.color_transform);
+        self.property(
+            "TextureSlot",
+            &emitter.texture_slot,
+            &emitter.texture_slot_transform,
+        );
+        self.animated("Visibility", &emitter.visibility_transform);
+        self.line(format!("EmissionRate {},", emitter.emission_rate));
+        self.line(format!("LifeSpan {},", self.value(&emitter.life_span)));
+        self.line(format!("Gravity {},", self.value(&emitter.gravity)));
+        self.line(format!("Rows {},", emitter.rows));
+        self.line(format!("Columns {},", emitter.columns));
+        self.line(format!("MaterialID {},", emitter.material_id));
+        self.unknown_tracks(&emitter.unknown_tracks);
+        self.close();
+    }
+
+    fn write_event_object(&mut self, event_object: &EventObject) {
+        self.open(format!("EventObject {}", quoted(&event_object.node.name)));
+        self.node(&event_object.node, &[]);
+        if let Some(keys) = &event_object.keys {
+            self.open(format!("EventTrack {}", keys.len()));
+            if let Some(global_sequence_id) = event_object.global_sequence_id {
+                self.line(format!("GlobalSeqId {},", global_sequence_id.0));
+            }
+            for key in keys {
+                self.line(format!("{},", key));
+            }
+            self.close();
+        }
+        self.close();
+    }
+
+    fn write_camera(&mut self, camera: &Camera) {
+        self.open(format!("Camera {}", quoted(&camera.name)));
+        self.line(format!("Position {},", self.value(&camera.position)));
+        self.animated("Translation", &camera.translation);
+        self.animated("Rotation", &camera.rotation);
+        self.line(format!(
+            "FieldOfView {},",
+            self.value(&camera.field_of_view)
+        ));
+        self.line(format!(
+            "FarClip {},",
+            self.value(&camera.far_clipping_plane)
+        ));
+        self.line(format!(
+            "NearClip {},",
+            self.value(&camera.near_clipping_plane)
+        ));
+        self.open("Target");
+        self.line(format!("Position {},", self.value(&camera.target_position)));
+        self.animated("Translation", &camera.target_translation);
+        self.close();
+        self.unknown_tracks(&camera.unknown_tracks);
+        self.close();
+    }
+
+    fn write_collision_shape(&mut self, collision_shape: &CollisionShape) {
+        self.open(format!(
+            "CollisionShape {}",
+            quoted(&collision_shape.node.name)
+        ));
+        self.node(&collision_shape.node, &[]);
+        let (name, vertices, radius) = match &collision_shape.shape {
+            Shape::Box { minimum, maximum } => ("Box", vec![minimum, maximum], None),
+            Shape::Plane { minimum, maximum } => ("Plane", vec![minimum, maximum], None),
+            Shape::Sphere { center, radius } => ("Sphere", vec![center], Some(radius)),
+            Shape::Cylinder { base, top, radius } => ("Cylinder", vec![base, top], Some(radius)),
+        };
+        self.line(format!("{},", name));
+        self.open(format!("Vertices {}", vertices.len()));
+        for vertex in vertices {
+            self.line(format!("{},", self.value(vertex)));
+        }
+        self.close();
+        if let Some(radius) = radius {
+            self.line(format!("BoundsRadius {},", self.value(radius)));
+        }
+        self.close();
+    }
+}
+
+// Fails on data MDL can't express instead of dropping it
+pub fn write_mdl(model: &MDLXModel) -> Result<String, MdlError> {
+    let mut writer = MdlWriter {
+        text: String::new(),
+        indent: 0,
+        error: None,
+        non_finite: Cell::new(false),
+    };
+    writer.write(model);
+    match writer.error {
+        Some(error) => Err(error),
+        None => Ok(writer.text),
+    }
+}
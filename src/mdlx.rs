@@ -1,6 +1,8 @@
 use crate::chunks::*;
 use crate::consts::*;
-use scroll::{Pread, Pwrite, LE};
+use crate::error::{read_at, FourCC, MdxError, MdxErrorKind};
+use scroll::{ctx, Endian, Pread, Pwrite, LE};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 #[derive(PartialEq, Debug, Default)]
 pub struct MDLXModel {
@@ -23,13 +25,27 @@ pub struct MDLXModel {
     pub event_object_chunk: Option<EventObjectChunk>,
     pub camera_chunk: Option<CameraChunk>,
     pub collision_shape_chunk: Option<CollisionShapeChunk>,
+    pub popcorn_emitter_chunk: Option<PopcornEmitterChunk>,
     pub material_chunk: Option<MaterialChunk>,
+    pub face_effect_chunk: Option<FaceEffectChunk>,
+    pub bind_pose_chunk: Option<BindPoseChunk>,
+    // Chunks with unrecognised tags, in the order they appeared
+    pub unknown_chunks: Vec<UnknownChunk>,
+    // Tags in the order chunks were read, empty for new models
+    pub chunk_order: Vec<u32>,
 }
 
+// Order chunks of new models are written in
+const CANONICAL_CHUNK_ORDER: [u32; 23] = [
+    VERS_TAG, MODL_TAG, SEQS_TAG, GLBS_TAG, TEXS_TAG, MTLS_TAG, TXAN_TAG, GEOS_TAG, GEOA_TAG,
+    BONE_TAG, LITE_TAG, HELP_TAG, ATCH_TAG, PIVT_TAG, PREM_TAG, PRE2_TAG, CORN_TAG, RIBB_TAG,
+    CAMS_TAG, EVTS_TAG, CLID_TAG, FAFX_TAG, BPOS_TAG,
+];
+
 impl MDLXModel {
-    pub fn read_mdx_file(data: Vec<u8>) -> Result<MDLXModel, scroll::Error> {
+    pub fn read_mdx_file(data: Vec<u8>) -> Result<MDLXModel, MdxError> {
         let offset = &mut 0usize;
-        let mdlx_tag = data.gread_with::<u32>(offset, LE)?;
+        let mdlx_tag = read_at::<u32, _>(&data, offset, LE)?;
         if mdlx_tag == MDLX_TAG {
             let mut result = MDLXModel::default();
 
@@ -37,354 +53,293 @@ impl MDLXModel {
             while *offset < data.len() {
                 info!("Offset: {}", &offset);
 
-                // For debug
-                let mut tag_offset = offset.clone();
-                let tag_buffer = (0..4)
-                    .map(|_| data.gread::<u8>(&mut tag_offset).unwrap())
-                    .collect::<Vec<u8>>();
-                let tag_name = String::from_utf8(tag_buffer).unwrap_or("NOTAG".to_string());
-
-                let tag = data.gread_with::<u32>(offset, LE)?;
-                info!("TagHex: {}", format!("{:X}", &tag));
+                let tag = read_at::<u32, _>(&data, offset, LE)?;
+                info!("TagHex: {:X}", &tag);
                 info!("TagDec: {}", &tag);
-                info!("TagName: {}", &tag_name);
+                info!("TagName: {}", FourCC(tag));
 
-                result.handle_tag(tag, &data, offset)?;
+                result.chunk_order.push(tag);
+
+                let start = *offset;
+                result
+                    .handle_tag(tag, &data, offset)
+                    .map_err(|e| e.in_chunk(start, tag))?;
             }
 
             Ok(result)
         } else {
-            Err(scroll::Error::Custom("Not correct MDLX file".to_string()))
+            Err(MdxError::new(0, MdxErrorKind::NotMdx))
         }
     }
 
-    pub fn write_mdx_file(mut model: MDLXModel) -> Result<Vec<u8>, scroll::Error> {
-        // Get total size of mdx file
-        let total_size = model.model_total_size();
-        model.correct_chunk_size();
-
-        // Create vec with capacity and set it len to total size
-        let mut data = Vec::<u8>::with_capacity(total_size);
-        unsafe {
-            data.set_len(total_size);
-        }
+    // Reads chunk by chunk, so only one chunk is held in memory at a time.
+    pub fn read_from<R: Read>(reader: R) -> Result<MDLXModel, MdxError> {
+        MDLXModel::read_chunks(reader, None)
+    }
 
-        // Begin to write fields
-        let offset = &mut 0usize;
+    // Like read_from, but chunk sizes past the end of the stream fail before reading them.
+    pub fn read_from_seekable<R: Read + Seek>(mut reader: R) -> Result<MDLXModel, MdxError> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
 
-        data.gwrite_with::<u32>(MDLX_TAG, offset, LE)?;
+        MDLXModel::read_chunks(reader, Some(end.saturating_sub(start)))
+    }
 
-        if model.version_chunk.is_some() {
-            data.gwrite_with::<u32>(VERS_TAG, offset, LE)?;
-            data.gwrite_with::<VersionChunk>(model.version_chunk.unwrap(), offset, LE)?;
-        }
-        if model.model_chunk.is_some() {
-            data.gwrite_with::<u32>(MODL_TAG, offset, LE)?;
-            data.gwrite_with::<ModelChunk>(model.model_chunk.unwrap(), offset, LE)?;
-        }
-        if model.sequence_chunk.is_some() {
-            data.gwrite_with::<u32>(SEQS_TAG, offset, LE)?;
-            data.gwrite_with::<SequenceChunk>(model.sequence_chunk.unwrap(), offset, LE)?;
-        }
-        if model.global_sequence_chunk.is_some() {
-            data.gwrite_with::<u32>(GLBS_TAG, offset, LE)?;
-            data.gwrite_with::<GlobalSequenceChunk>(
-                model.global_sequence_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.texture_chunk.is_some() {
-            data.gwrite_with::<u32>(TEXS_TAG, offset, LE)?;
-            data.gwrite_with::<TextureChunk>(model.texture_chunk.unwrap(), offset, LE)?;
-        }
-        if model.texture_animation_chunk.is_some() {
-            data.gwrite_with::<u32>(TXAN_TAG, offset, LE)?;
-            data.gwrite_with::<TextureAnimationChunk>(
-                model.texture_animation_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.geoset_chunk.is_some() {
-            data.gwrite_with::<u32>(GEOS_TAG, offset, LE)?;
-            data.gwrite_with::<GeosetChunk>(model.geoset_chunk.unwrap(), offset, LE)?;
+    fn read_chunks<R: Read>(mut reader: R, length: Option<u64>) -> Result<MDLXModel, MdxError> {
+        let mut mdlx_tag = [0u8; 4];
+        let read = read_full(&mut reader, &mut mdlx_tag)?;
+        if read < 4 {
+            return Err(MdxError::new(read, MdxErrorKind::UnexpectedEof));
         }
-        if model.geoset_animation_chunk.is_some() {
-            data.gwrite_with::<u32>(GEOA_TAG, offset, LE)?;
-            data.gwrite_with::<GeosetAnimationChunk>(
-                model.geoset_animation_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
+        if u32::from_le_bytes(mdlx_tag) != MDLX_TAG {
+            return Err(MdxError::new(0, MdxErrorKind::NotMdx));
         }
-        if model.bone_chunk.is_some() {
-            data.gwrite_with::<u32>(BONE_TAG, offset, LE)?;
-            data.gwrite_with::<BoneChunk>(model.bone_chunk.unwrap(), offset, LE)?;
-        }
-        if model.light_chunk.is_some() {
-            data.gwrite_with::<u32>(LITE_TAG, offset, LE)?;
-            data.gwrite_with::<LightChunk>(model.light_chunk.unwrap(), offset, LE)?;
-        }
-        if model.helper_chunk.is_some() {
-            data.gwrite_with::<u32>(HELP_TAG, offset, LE)?;
-            data.gwrite_with::<HelperChunk>(model.helper_chunk.unwrap(), offset, LE)?;
-        }
-        if model.attachment_chunk.is_some() {
-            data.gwrite_with::<u32>(ATCH_TAG, offset, LE)?;
-            data.gwrite_with::<AttachmentChunk>(model.attachment_chunk.unwrap(), offset, LE)?;
-        }
-        if model.pivot_point_chunk.is_some() {
-            data.gwrite_with::<u32>(PIVT_TAG, offset, LE)?;
-            data.gwrite_with::<PivotPointChunk>(model.pivot_point_chunk.unwrap(), offset, LE)?;
-        }
-        if model.particle_emitter_chunk.is_some() {
-            data.gwrite_with::<u32>(PREM_TAG, offset, LE)?;
-            data.gwrite_with::<ParticleEmitterChunk>(
-                model.particle_emitter_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.particle_emitter2_chunk.is_some() {
-            data.gwrite_with::<u32>(PRE2_TAG, offset, LE)?;
-            data.gwrite_with::<ParticleEmitter2Chunk>(
-                model.particle_emitter2_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.ribbon_emitter_chunk.is_some() {
-            data.gwrite_with::<u32>(RIBB_TAG, offset, LE)?;
-            data.gwrite_with::<RibbonEmitterChunk>(
-                model.ribbon_emitter_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.event_object_chunk.is_some() {
-            data.gwrite_with::<u32>(EVTS_TAG, offset, LE)?;
-            data.gwrite_with::<EventObjectChunk>(model.event_object_chunk.unwrap(), offset, LE)?;
-        }
-        if model.camera_chunk.is_some() {
-            data.gwrite_with::<u32>(CAMS_TAG, offset, LE)?;
-            data.gwrite_with::<CameraChunk>(model.camera_chunk.unwrap(), offset, LE)?;
-        }
-        if model.collision_shape_chunk.is_some() {
-            data.gwrite_with::<u32>(CLID_TAG, offset, LE)?;
-            data.gwrite_with::<CollisionShapeChunk>(
-                model.collision_shape_chunk.unwrap(),
-                offset,
-                LE,
-            )?;
-        }
-        if model.material_chunk.is_some() {
-            data.gwrite_with::<u32>(MTLS_TAG, offset, LE)?;
-            data.gwrite_with::<MaterialChunk>(model.material_chunk.unwrap(), offset, LE)?;
+
+        let mut result = MDLXModel::default();
+        let mut position = 4usize;
+        loop {
+            // Tag and chunk_size
+            let mut header = [0u8; 8];
+            let read = read_full(&mut reader, &mut header)
+                .map_err(|e| MdxError::from(e).offset_by(position))?;
+            if read == 0 {
+                break;
+            }
+            if read < 4 {
+                return Err(MdxError::new(position + read, MdxErrorKind::UnexpectedEof));
+            }
+
+            let tag = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+            info!("TagName: {}", FourCC(tag));
+            position += 4;
+
+            let eof = |offset: usize| MdxError::new(offset, MdxErrorKind::UnexpectedEof);
+            if read < 8 {
+                return Err(eof(read - 4).in_chunk(position, tag));
+            }
+
+            let chunk_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            if let Some(length) = length {
+                if (position + 4) as u64 + u64::from(chunk_size) > length {
+                    return Err(eof(4).in_chunk(position, tag));
+                }
+            }
+
+            // Chunk readers expect chunk_size in front of the data
+            let mut chunk = header[4..].to_vec();
+            reader
+                .by_ref()
+                .take(u64::from(chunk_size))
+                .read_to_end(&mut chunk)
+                .map_err(|e| MdxError::from(e).in_chunk(position, tag))?;
+            if chunk.len() < 4 + chunk_size as usize {
+                return Err(eof(chunk.len()).in_chunk(position, tag));
+            }
+
+            result.chunk_order.push(tag);
+            result
+                .handle_tag(tag, &chunk, &mut 0)
+                .map_err(|e| e.in_chunk(position, tag))?;
+
+            position += chunk.len();
         }
 
-        // Return result
+        Ok(result)
+    }
+
+    pub fn read_mdl_file(text: &str) -> Result<MDLXModel, crate::mdl::MdlError> {
+        crate::mdl::read_mdl(text)
+    }
+
+    pub fn write_mdl_file(model: &MDLXModel) -> Result<String, crate::mdl::MdlError> {
+        crate::mdl::write_mdl(model)
+    }
+
+    pub fn write_mdx_file(model: MDLXModel) -> Result<Vec<u8>, scroll::Error> {
+        let mut data = Vec::new();
+        model.write_to(&mut data)?;
         Ok(data)
     }
 
-    fn correct_chunk_size(&mut self) {
-        if self.version_chunk.is_some() {
-            let version = self.version_chunk.as_mut().unwrap();
-            version.calculate_chunk_size();
-        }
-        if self.model_chunk.is_some() {
-            let model = self.model_chunk.as_mut().unwrap();
-            model.calculate_chunk_size();
-        }
-        if self.sequence_chunk.is_some() {
-            let sequence = self.sequence_chunk.as_mut().unwrap();
-            sequence.calculate_chunk_size();
-        }
-        if self.global_sequence_chunk.is_some() {
-            let global_sequence = self.global_sequence_chunk.as_mut().unwrap();
-            global_sequence.calculate_chunk_size();
-        }
-        if self.texture_chunk.is_some() {
-            let texture = self.texture_chunk.as_mut().unwrap();
-            texture.calculate_chunk_size();
-        }
-        if self.texture_animation_chunk.is_some() {
-            let texture_animation = self.texture_animation_chunk.as_mut().unwrap();
-            texture_animation.calculate_chunk_size();
-        }
-        if self.geoset_chunk.is_some() {
-            let geoset = self.geoset_chunk.as_mut().unwrap();
-            geoset.calculate_chunk_size();
-        }
-        if self.geoset_animation_chunk.is_some() {
-            let geoset_animation = self.geoset_animation_chunk.as_mut().unwrap();
-            geoset_animation.calculate_chunk_size();
-        }
-        if self.bone_chunk.is_some() {
-            let bone = self.bone_chunk.as_mut().unwrap();
-            bone.calculate_chunk_size();
-        }
-        if self.light_chunk.is_some() {
-            let light = self.light_chunk.as_mut().unwrap();
-            light.calculate_chunk_size();
-        }
-        if self.helper_chunk.is_some() {
-            let helper = self.helper_chunk.as_mut().unwrap();
-            helper.calculate_chunk_size();
+    // Writes chunk by chunk, so only one serialised chunk is held in memory at a time. Fails
+    // instead of dropping tangents or skin weights when the version is too old for them.
+    pub fn write_to<W: Write>(mut self, mut writer: W) -> Result<(), scroll::Error> {
+        // Layout of some chunks depends on the version
+        self.match_version()?;
+        // A typed chunk and an unknown one with its tag would both be written
+        if let Some(unknown_chunk) = self
+            .unknown_chunks
+            .iter()
+            .find(|chunk| CANONICAL_CHUNK_ORDER.contains(&chunk.tag))
+        {
+            return Err(scroll::Error::Custom(format!(
+                "unknown chunk has the tag of the typed chunk {}",
+                FourCC(unknown_chunk.tag)
+            )));
         }
-        if self.attachment_chunk.is_some() {
-            let attachment = self.attachment_chunk.as_mut().unwrap();
-            attachment.calculate_chunk_size();
-        }
-        if self.pivot_point_chunk.is_some() {
-            let pivot = self.pivot_point_chunk.as_mut().unwrap();
-            pivot.calculate_chunk_size();
-        }
-        if self.particle_emitter_chunk.is_some() {
-            let particle_emitter = self.particle_emitter_chunk.as_mut().unwrap();
-            particle_emitter.calculate_chunk_size();
-        }
-        if self.particle_emitter2_chunk.is_some() {
-            let particle_emitter2 = self.particle_emitter2_chunk.as_mut().unwrap();
-            particle_emitter2.calculate_chunk_size();
-        }
-        if self.ribbon_emitter_chunk.is_some() {
-            let ribbon_emitter = self.ribbon_emitter_chunk.as_mut().unwrap();
-            ribbon_emitter.calculate_chunk_size();
-        }
-        if self.event_object_chunk.is_some() {
-            let event_object = self.event_object_chunk.as_mut().unwrap();
-            event_object.calculate_chunk_size();
-        }
-        if self.camera_chunk.is_some() {
-            let camera = self.camera_chunk.as_mut().unwrap();
-            camera.calculate_chunk_size();
-        }
-        if self.collision_shape_chunk.is_some() {
-            let collision_shape = self.collision_shape_chunk.as_mut().unwrap();
-            collision_shape.calculate_chunk_size();
+
+        writer.write_all(&MDLX_TAG.to_le_bytes())?;
+
+        for tag in self.write_order() {
+            if let Some(bytes) = self.chunk_bytes(tag)? {
+                writer.write_all(&bytes)?;
+            }
         }
-        if self.material_chunk.is_some() {
-            let material = self.material_chunk.as_mut().unwrap();
-            material.calculate_chunk_size();
+        // Unknown chunks added after reading have no place yet
+        for unknown_chunk in std::mem::take(&mut self.unknown_chunks) {
+            writer.write_all(&chunk_bytes(unknown_chunk.tag, unknown_chunk)?)?;
         }
+
+        writer.flush()?;
+        Ok(())
     }
 
-    fn model_total_size(&self) -> usize {
-        let mut result = 0usize;
-        // MDLX_TAG
-        result += 4;
-        // Tag + size of bytes inside structs
-        if self.version_chunk.is_some() {
-            let version = self.version_chunk.as_ref().unwrap();
-            result += 4;
-            result += version.total_bytes_size();
-        }
-        if self.model_chunk.is_some() {
-            let model = self.model_chunk.as_ref().unwrap();
-            result += 4;
-            result += model.total_bytes_size();
-        }
-        if self.sequence_chunk.is_some() {
-            let sequence = self.sequence_chunk.as_ref().unwrap();
-            result += 4;
-            result += sequence.total_bytes_size();
-        }
-        if self.global_sequence_chunk.is_some() {
-            let global_sequence = self.global_sequence_chunk.as_ref().unwrap();
-            result += 4;
-            result += global_sequence.total_bytes_size();
-        }
-        if self.texture_chunk.is_some() {
-            let texture = self.texture_chunk.as_ref().unwrap();
-            result += 4;
-            result += texture.total_bytes_size();
-        }
-        if self.texture_animation_chunk.is_some() {
-            let texture_animation = self.texture_animation_chunk.as_ref().unwrap();
-            result += 4;
-            result += texture_animation.total_bytes_size();
-        }
-        if self.geoset_chunk.is_some() {
-            let geoset = self.geoset_chunk.as_ref().unwrap();
-            result += 4;
-            result += geoset.total_bytes_size();
-        }
-        if self.geoset_animation_chunk.is_some() {
-            let geoset_animation = self.geoset_animation_chunk.as_ref().unwrap();
-            result += 4;
-            result += geoset_animation.total_bytes_size();
-        }
-        if self.bone_chunk.is_some() {
-            let bone = self.bone_chunk.as_ref().unwrap();
-            result += 4;
-            result += bone.total_bytes_size();
-        }
-        if self.light_chunk.is_some() {
-            let light = self.light_chunk.as_ref().unwrap();
-            result += 4;
-            result += light.total_bytes_size();
-        }
-        if self.helper_chunk.is_some() {
-            let helper = self.helper_chunk.as_ref().unwrap();
-            result += 4;
-            result += helper.total_bytes_size();
-        }
-        if self.attachment_chunk.is_some() {
-            let attachment = self.attachment_chunk.as_ref().unwrap();
-            result += 4;
-            result += attachment.total_bytes_size();
-        }
-        if self.pivot_point_chunk.is_some() {
-            let pivot = self.pivot_point_chunk.as_ref().unwrap();
-            result += 4;
-            result += pivot.total_bytes_size();
-        }
-        if self.particle_emitter_chunk.is_some() {
-            let particle_emitter = self.particle_emitter_chunk.as_ref().unwrap();
-            result += 4;
-            result += particle_emitter.total_bytes_size();
-        }
-        if self.particle_emitter2_chunk.is_some() {
-            let particle_emitter2 = self.particle_emitter2_chunk.as_ref().unwrap();
-            result += 4;
-            result += particle_emitter2.total_bytes_size();
-        }
-        if self.ribbon_emitter_chunk.is_some() {
-            let ribbon_emitter = self.ribbon_emitter_chunk.as_ref().unwrap();
-            result += 4;
-            result += ribbon_emitter.total_bytes_size();
-        }
-        if self.event_object_chunk.is_some() {
-            let event_object = self.event_object_chunk.as_ref().unwrap();
-            result += 4;
-            result += event_object.total_bytes_size();
-        }
-        if self.camera_chunk.is_some() {
-            let camera = self.camera_chunk.as_ref().unwrap();
-            result += 4;
-            result += camera.total_bytes_size();
+    // Chunks are written in the order they were read, new ones follow in canonical order.
+    fn write_order(&self) -> Vec<u32> {
+        let mut order = self.chunk_order.clone();
+        for tag in CANONICAL_CHUNK_ORDER.iter() {
+            if !order.contains(tag) {
+                order.push(*tag);
+            }
         }
-        if self.collision_shape_chunk.is_some() {
-            let collision_shape = self.collision_shape_chunk.as_ref().unwrap();
-            result += 4;
-            result += collision_shape.total_bytes_size();
+        order
+    }
+
+    // Serialises the chunk with the given tag if the model has it, taking it out of the model.
+    fn chunk_bytes(&mut self, tag: u32) -> Result<Option<Vec<u8>>, scroll::Error> {
+        let bytes = match tag {
+            VERS_TAG => self
+                .version_chunk
+                .take()
+                .map(|version_chunk| chunk_bytes(VERS_TAG, version_chunk)),
+            MODL_TAG => self
+                .model_chunk
+                .take()
+                .map(|model_chunk| chunk_bytes(MODL_TAG, model_chunk)),
+            SEQS_TAG => self
+                .sequence_chunk
+                .take()
+                .map(|sequence_chunk| chunk_bytes(SEQS_TAG, sequence_chunk)),
+            GLBS_TAG => self
+                .global_sequence_chunk
+                .take()
+                .map(|global_sequence_chunk| chunk_bytes(GLBS_TAG, global_sequence_chunk)),
+            TEXS_TAG => self
+                .texture_chunk
+                .take()
+                .map(|texture_chunk| chunk_bytes(TEXS_TAG, texture_chunk)),
+            MTLS_TAG => self
+                .material_chunk
+                .take()
+                .map(|material_chunk| chunk_bytes(MTLS_TAG, material_chunk)),
+            TXAN_TAG => self
+                .texture_animation_chunk
+                .take()
+                .map(|texture_animation_chunk| chunk_bytes(TXAN_TAG, texture_animation_chunk)),
+            GEOS_TAG => self
+                .geoset_chunk
+                .take()
+                .map(|geoset_chunk| chunk_bytes(GEOS_TAG, geoset_chunk)),
+            GEOA_TAG => self
+                .geoset_animation_chunk
+                .take()
+                .map(|geoset_animation_chunk| chunk_bytes(GEOA_TAG, geoset_animation_chunk)),
+            BONE_TAG => self
+                .bone_chunk
+                .take()
+                .map(|bone_chunk| chunk_bytes(BONE_TAG, bone_chunk)),
+            LITE_TAG => self
+                .light_chunk
+                .take()
+                .map(|light_chunk| chunk_bytes(LITE_TAG, light_chunk)),
+            HELP_TAG => self
+                .helper_chunk
+                .take()
+                .map(|helper_chunk| chunk_bytes(HELP_TAG, helper_chunk)),
+            ATCH_TAG => self
+                .attachment_chunk
+                .take()
+                .map(|attachment_chunk| chunk_bytes(ATCH_TAG, attachment_chunk)),
+            PIVT_TAG => self
+                .pivot_point_chunk
+                .take()
+                .map(|pivot_point_chunk| chunk_bytes(PIVT_TAG, pivot_point_chunk)),
+            PREM_TAG => self
+                .particle_emitter_chunk
+                .take()
+                .map(|particle_emitter_chunk| chunk_bytes(PREM_TAG, particle_emitter_chunk)),
+            PRE2_TAG => self
+                .particle_emitter2_chunk
+                .take()
+                .map(|particle_emitter2_chunk| chunk_bytes(PRE2_TAG, particle_emitter2_chunk)),
+            CORN_TAG => self
+                .popcorn_emitter_chunk
+                .take()
+                .map(|popcorn_emitter_chunk| chunk_bytes(CORN_TAG, popcorn_emitter_chunk)),
+            RIBB_TAG => self
+                .ribbon_emitter_chunk
+                .take()
+                .map(|ribbon_emitter_chunk| chunk_bytes(RIBB_TAG, ribbon_emitter_chunk)),
+            CAMS_TAG => self
+                .camera_chunk
+                .take()
+                .map(|camera_chunk| chunk_bytes(CAMS_TAG, camera_chunk)),
+            EVTS_TAG => self
+                .event_object_chunk
+                .take()
+                .map(|event_object_chunk| chunk_bytes(EVTS_TAG, event_object_chunk)),
+            CLID_TAG => self
+                .collision_shape_chunk
+                .take()
+                .map(|collision_shape_chunk| chunk_bytes(CLID_TAG, collision_shape_chunk)),
+            FAFX_TAG => self
+                .face_effect_chunk
+                .take()
+                .map(|face_effect_chunk| chunk_bytes(FAFX_TAG, face_effect_chunk)),
+            BPOS_TAG => self
+                .bind_pose_chunk
+                .take()
+                .map(|bind_pose_chunk| chunk_bytes(BPOS_TAG, bind_pose_chunk)),
+            _ => self.unknown_chunk_bytes(tag),
+        };
+        bytes.transpose()
+    }
+
+    fn unknown_chunk_bytes(&mut self, tag: u32) -> Option<Result<Vec<u8>, scroll::Error>> {
+        let index = self
+            .unknown_chunks
+            .iter()
+            .position(|chunk| chunk.tag == tag)?;
+        let unknown_chunk = self.unknown_chunks.remove(index);
+        Some(chunk_bytes(tag, unknown_chunk))
+    }
+
+    pub fn bind_matrix(&self, object_id: u32) -> Option<&BindMatrix> {
+        self.bind_pose_chunk
+            .as_ref()
+            .and_then(|bind_pose| bind_pose.bind_matrix(object_id))
+    }
+
+    // Format version from VERS, files without it are treated as 800
+    pub fn version(&self) -> u32 {
+        self.version_chunk
+            .as_ref()
+            .map_or(800, |version_chunk| version_chunk.version)
+    }
+
+    pub(crate) fn match_version(&mut self) -> Result<(), scroll::Error> {
+        let version = self.version();
+        if let Some(geoset) = self.geoset_chunk.as_mut() {
+            geoset.match_version(version)?;
         }
-        if self.material_chunk.is_some() {
-            let material = self.material_chunk.as_ref().unwrap();
-            result += 4;
-            result += material.total_bytes_size();
+        if let Some(material) = self.material_chunk.as_mut() {
+            material.match_version(version)?;
         }
-
-        result
+        Ok(())
     }
 
-    fn handle_tag(
-        &mut self,
-        tag: u32,
-        data: &[u8],
-        offset: &mut usize,
-    ) -> Result<(), scroll::Error> {
+    fn handle_tag(&mut self, tag: u32, data: &[u8], offset: &mut usize) -> Result<(), MdxError> {
         match tag {
             VERS_TAG => {
                 let version_chunk = data.gread_with::<VersionChunk>(offset, LE)?;
@@ -412,7 +367,7 @@ impl MDLXModel {
                 self.texture_animation_chunk = Some(texture_animation_chunk);
             }
             GEOS_TAG => {
-                let geoset_chunk = data.gread_with::<GeosetChunk>(offset, LE)?;
+                let geoset_chunk = data.gread_with::<GeosetChunk>(offset, (LE, self.version()))?;
                 self.geoset_chunk = Some(geoset_chunk);
             }
             GEOA_TAG => {
@@ -464,12 +419,55 @@ impl MDLXModel {
                 let collision_shape_chunk = data.gread_with::<CollisionShapeChunk>(offset, LE)?;
                 self.collision_shape_chunk = Some(collision_shape_chunk);
             }
+            CORN_TAG => {
+                let popcorn_emitter_chunk = data.gread_with::<PopcornEmitterChunk>(offset, LE)?;
+                self.popcorn_emitter_chunk = Some(popcorn_emitter_chunk);
+            }
             MTLS_TAG => {
-                let material_chunk = data.gread_with::<MaterialChunk>(offset, LE)?;
+                let material_chunk =
+                    data.gread_with::<MaterialChunk>(offset, (LE, self.version()))?;
                 self.material_chunk = Some(material_chunk);
             }
-            _ => unreachable!(),
+            FAFX_TAG => {
+                let face_effect_chunk = data.gread_with::<FaceEffectChunk>(offset, LE)?;
+                self.face_effect_chunk = Some(face_effect_chunk);
+            }
+            BPOS_TAG => {
+                let bind_pose_chunk = data.gread_with::<BindPoseChunk>(offset, LE)?;
+                self.bind_pose_chunk = Some(bind_pose_chunk);
+            }
+            _ => {
+                let unknown_chunk = data.gread_with::<UnknownChunk>(offset, (tag, LE))?;
+                self.unknown_chunks.push(unknown_chunk);
+            }
         }
         Ok(())
     }
 }
+
+// Fills buf as far as the reader allows, returns how many bytes were read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+// Tag followed by the chunk, sized from the chunk itself.
+fn chunk_bytes<T>(tag: u32, chunk: T) -> Result<Vec<u8>, scroll::Error>
+where
+    T: BytesTotalSize + ctx::TryIntoCtx<Endian, Error = scroll::Error>,
+{
+    let mut data = vec![0u8; 4 + chunk.total_bytes_size()];
+    let offset = &mut 0usize;
+    data.gwrite_with::<u32>(tag, offset, LE)?;
+    data.gwrite_with::<T>(chunk, offset, LE)?;
+    data.truncate(*offset);
+    Ok(data)
+}